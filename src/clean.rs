@@ -0,0 +1,322 @@
+use crate::{
+    console_utils, editor,
+    errors::KataError,
+    file_utils::collect_source_files,
+    history,
+    languages::{
+        csharp::solution::plan_csharp_solution, rust::cargo_workspace::plan_cargo_workspace,
+    },
+    list_day_folders,
+    time_utils::unix_timestamp,
+    Kata, Language, Session,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Which days `clean` removes and how. Without filters every day is removed.
+#[derive(Default)]
+pub struct CleanOptions {
+    pub language: Option<Language>,
+    pub kata: Option<Kata>,
+    /// Only remove days that were last modified longer ago than this.
+    pub older_than: Option<Duration>,
+    /// Always keep the most recent days of every kata.
+    pub keep_last: Option<usize>,
+    pub dry_run: bool,
+    /// Move everything to `.kata/trash` instead of deleting it.
+    pub trash: bool,
+    pub skip_confirmation: bool,
+}
+
+impl CleanOptions {
+    fn removes_everything(&self) -> bool {
+        self.language.is_none()
+            && self.kata.is_none()
+            && self.older_than.is_none()
+            && self.keep_last.is_none()
+    }
+}
+
+pub fn clean(root: &Path, options: &CleanOptions) -> Result<(), KataError> {
+    let days = select_days(root, options)?;
+
+    let mut targets: Vec<PathBuf> = Vec::new();
+    for (language, kata, day_folder_path) in &days {
+        targets.push(day_folder_path.clone());
+        let day = day_folder_path.file_name().unwrap().to_string_lossy();
+        for path in [
+            history::history_path(root, *language, *kata, &day),
+            history::pairing_path(root, *language, *kata, &day),
+            history::template_path(root, *language, *kata, &day),
+        ] {
+            if path.exists() {
+                targets.push(path);
+            }
+        }
+    }
+    if options.removes_everything() {
+        for path in [
+            history::objects_dir(root),
+            root.join(".kata").join("staging"),
+        ] {
+            if path.exists() {
+                targets.push(path);
+            }
+        }
+    }
+
+    let session_path = root.join("session.json");
+    if session_path.exists() && session_points_into(root, &days)? {
+        targets.push(session_path);
+    }
+
+    let removed_days: Vec<PathBuf> = days.iter().map(|(_, _, path)| path.clone()).collect();
+    let cargo_sync = plan_cargo_workspace(root, &removed_days, false)?;
+    if options.removes_everything() && cargo_sync.is_some() {
+        let rust_dir = root.join(Language::Rust.as_str());
+        for path in [rust_dir.join("target"), rust_dir.join("Cargo.lock")] {
+            if path.exists() {
+                targets.push(path);
+            }
+        }
+    }
+    let solution_sync = plan_csharp_solution(root, &removed_days)?;
+    if options.removes_everything() {
+        let typescript_dir = root.join(Language::TS.as_str());
+        for name in ["node_modules", "package.json", "package-lock.json"] {
+            let path = typescript_dir.join(name);
+            if path.exists() {
+                targets.push(path);
+            }
+        }
+    }
+    let editor_sync = editor::plan_sync(root, None, &removed_days)?;
+
+    if targets.is_empty()
+        && cargo_sync.is_none()
+        && solution_sync.is_none()
+        && editor_sync.is_empty()
+    {
+        println!("Nothing to clean.");
+        return Ok(());
+    }
+
+    let action = if options.trash {
+        "Move to trash"
+    } else {
+        "Remove"
+    };
+    for target in &targets {
+        println!("{} {}", action, display_path(root, target));
+    }
+    if let Some(cargo_sync) = &cargo_sync {
+        for line in &cargo_sync.summary {
+            println!("Update {}: {}", display_path(root, &cargo_sync.path), line);
+        }
+    }
+    if let Some(solution_sync) = &solution_sync {
+        for line in &solution_sync.summary {
+            println!(
+                "Update {}: {}",
+                display_path(root, &solution_sync.path),
+                line
+            );
+        }
+    }
+    for change in &editor_sync.changes {
+        for line in &change.summary {
+            println!("Update {}: {}", display_path(root, &change.path), line);
+        }
+    }
+
+    if options.dry_run {
+        println!("Dry run, nothing was changed.");
+        return Ok(());
+    }
+
+    if !options.skip_confirmation
+        && !console_utils::confirm(&format!("Clean {} day folder(s)?", days.len()))?
+    {
+        println!("Clean cancelled.");
+        return Ok(());
+    }
+
+    let trash_dir = options.trash.then(|| {
+        root.join(".kata")
+            .join("trash")
+            .join(unix_timestamp().to_string())
+    });
+
+    if let Some(cargo_sync) = &cargo_sync {
+        if let Some(trash_dir) = &trash_dir {
+            copy_to_trash(root, trash_dir, &cargo_sync.path)?;
+        }
+        cargo_sync.apply()?;
+    }
+    if let Some(solution_sync) = &solution_sync {
+        if let Some(trash_dir) = &trash_dir {
+            for path in solution_sync.paths(root) {
+                if path.exists() {
+                    copy_to_trash(root, trash_dir, &path)?;
+                }
+            }
+        }
+        solution_sync.apply(root)?;
+    }
+    if let Some(trash_dir) = &trash_dir {
+        for change in &editor_sync.changes {
+            if change.path.exists() {
+                copy_to_trash(root, trash_dir, &change.path)?;
+            }
+        }
+    }
+    editor_sync.apply(root)?;
+
+    for target in &targets {
+        match &trash_dir {
+            Some(trash_dir) => move_to_trash(root, trash_dir, target)?,
+            None => remove(target)?,
+        }
+        remove_empty_parents(root, target)?;
+    }
+
+    match &trash_dir {
+        Some(trash_dir) => println!(
+            "Moved {} item(s) to {}. Move them back to restore them.",
+            targets.len(),
+            display_path(root, trash_dir)
+        ),
+        None => println!("Cleanup completed successfully."),
+    }
+    Ok(())
+}
+
+fn select_days(
+    root: &Path,
+    options: &CleanOptions,
+) -> Result<Vec<(Language, Kata, PathBuf)>, KataError> {
+    let now = SystemTime::now();
+    let mut selected = Vec::new();
+
+    for language in Language::ALL {
+        if options.language.is_some_and(|l| l != language) {
+            continue;
+        }
+        for kata in Kata::ALL {
+            if options.kata.is_some_and(|k| k != kata) {
+                continue;
+            }
+
+            let kata_dir = root.join(language.as_str()).join(kata.as_str());
+            let mut days = list_day_folders(&kata_dir)?;
+            let keep = options.keep_last.unwrap_or(0).min(days.len());
+            days.truncate(days.len() - keep);
+
+            for day_folder_path in days {
+                if let Some(older_than) = options.older_than {
+                    let age = now
+                        .duration_since(last_modified(&day_folder_path)?)
+                        .unwrap_or_default();
+                    if age < older_than {
+                        continue;
+                    }
+                }
+                selected.push((language, kata, day_folder_path));
+            }
+        }
+    }
+
+    Ok(selected)
+}
+
+/// The most recent modification of the day folder or any of its source files.
+fn last_modified(day_folder_path: &Path) -> Result<SystemTime, KataError> {
+    let modified = |path: &Path| {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|e| KataError::io(format!("Failed to read {}", path.display()), e))
+    };
+
+    let mut latest = modified(day_folder_path)?;
+    for relative_path in collect_source_files(day_folder_path)? {
+        latest = latest.max(modified(&day_folder_path.join(relative_path))?);
+    }
+    Ok(latest)
+}
+
+fn session_points_into(root: &Path, days: &[(Language, Kata, PathBuf)]) -> Result<bool, KataError> {
+    let session_json = Session::read_json(root)?;
+    // A corrupt session is useless, so it goes together with any day
+    let Ok(session) = Session::parse(root, &session_json) else {
+        return Ok(!days.is_empty());
+    };
+
+    Ok(days.iter().any(|(_, _, path)| session.day == *path))
+}
+
+fn move_to_trash(root: &Path, trash_dir: &Path, path: &Path) -> Result<(), KataError> {
+    let target = trash_dir.join(path.strip_prefix(root).unwrap_or(path));
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| KataError::io(format!("Failed to create {}", parent.display()), e))?;
+    }
+    fs::rename(path, &target).map_err(|e| {
+        KataError::io(
+            format!("Failed to move {} to the trash", display_path(root, path)),
+            e,
+        )
+    })
+}
+
+fn copy_to_trash(root: &Path, trash_dir: &Path, path: &Path) -> Result<(), KataError> {
+    let target = trash_dir.join(path.strip_prefix(root).unwrap_or(path));
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| KataError::io(format!("Failed to create {}", parent.display()), e))?;
+    }
+    fs::copy(path, &target).map(|_| ()).map_err(|e| {
+        KataError::io(
+            format!("Failed to copy {} to the trash", display_path(root, path)),
+            e,
+        )
+    })
+}
+
+fn remove(path: &Path) -> Result<(), KataError> {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    result.map_err(|e| KataError::io(format!("Failed to remove {}", path.display()), e))
+}
+
+/// Removes the kata and language folders left empty, stopping at the workspace root.
+fn remove_empty_parents(root: &Path, path: &Path) -> Result<(), KataError> {
+    let mut parent = path.parent();
+    while let Some(dir) = parent {
+        if dir == root || !dir.starts_with(root) {
+            break;
+        }
+        let is_empty = fs::read_dir(dir)
+            .map(|mut entries| entries.next().is_none())
+            .unwrap_or(false);
+        if !is_empty {
+            break;
+        }
+        fs::remove_dir(dir)
+            .map_err(|e| KataError::io(format!("Failed to remove {}", dir.display()), e))?;
+        parent = dir.parent();
+    }
+    Ok(())
+}
+
+fn display_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
@@ -0,0 +1,249 @@
+use crate::{
+    diff::{diff_stat, side_by_side_diff, unified_diff},
+    errors::KataError,
+    file_utils::collect_source_files,
+    CSharpTestFramework, FileRole, Language,
+};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const SIDE_BY_SIDE_WIDTH: usize = 60;
+
+/// Relative paths of the files to compare; `None` when a side has no matching file.
+type FilePair = (Option<String>, Option<String>);
+
+#[derive(Clone, Copy)]
+pub enum DiffFormat {
+    Unified,
+    SideBySide,
+    Stat,
+}
+
+pub struct DaySide {
+    pub language: Language,
+    pub day_folder_path: PathBuf,
+}
+
+pub fn compare_days(
+    root: &Path,
+    left: &DaySide,
+    right: &DaySide,
+    format: DiffFormat,
+) -> Result<(), KataError> {
+    let pairs = align_files(left, right)?;
+    let (mut total_added, mut total_removed) = (0, 0);
+
+    for (left_file, right_file) in &pairs {
+        let left_content = read_day_file(&left.day_folder_path, left_file.as_deref())?;
+        let right_content = read_day_file(&right.day_folder_path, right_file.as_deref())?;
+        let left_name = display_name(root, &left.day_folder_path, left_file.as_deref());
+        let right_name = display_name(root, &right.day_folder_path, right_file.as_deref());
+
+        match format {
+            DiffFormat::Unified => print!(
+                "{}",
+                unified_diff(&left_content, &right_content, &left_name, &right_name)
+            ),
+            DiffFormat::SideBySide => {
+                if left_content != right_content {
+                    println!("=== {} | {} ===", left_name, right_name);
+                    print!(
+                        "{}",
+                        side_by_side_diff(&left_content, &right_content, SIDE_BY_SIDE_WIDTH)
+                    );
+                }
+            }
+            DiffFormat::Stat => {
+                let (added, removed) = diff_stat(&left_content, &right_content);
+                total_added += added;
+                total_removed += removed;
+                if added + removed > 0 {
+                    println!("{} -> {} | +{} -{}", left_name, right_name, added, removed);
+                }
+            }
+        }
+    }
+
+    if let DiffFormat::Stat = format {
+        println!(
+            "{} file(s) compared, {} insertion(s)(+), {} deletion(s)(-)",
+            pairs.len(),
+            total_added,
+            total_removed
+        );
+
+        let left_tests = count_day_tests(left)?;
+        let right_tests = count_day_tests(right)?;
+        println!(
+            "Tests: {} -> {} ({:+})",
+            left_tests,
+            right_tests,
+            right_tests as i64 - left_tests as i64
+        );
+    }
+
+    Ok(())
+}
+
+/// Pairs up the files to compare. Days of the same language are aligned by path,
+/// days of different languages by the role of their solution and test files.
+fn align_files(left: &DaySide, right: &DaySide) -> Result<Vec<FilePair>, KataError> {
+    let left_files = day_files(&left.day_folder_path)?;
+    let right_files = day_files(&right.day_folder_path)?;
+
+    if left.language == right.language {
+        let paths: BTreeSet<&String> = left_files.iter().chain(right_files.iter()).collect();
+        return Ok(paths
+            .into_iter()
+            .map(|path| {
+                (
+                    left_files.contains(path).then(|| path.clone()),
+                    right_files.contains(path).then(|| path.clone()),
+                )
+            })
+            .collect());
+    }
+
+    let mut pairs = Vec::new();
+    for role in [FileRole::Solution, FileRole::Test] {
+        let left_role: Vec<&String> = left_files
+            .iter()
+            .filter(|path| left.language.file_role(path) == role)
+            .collect();
+        let right_role: Vec<&String> = right_files
+            .iter()
+            .filter(|path| right.language.file_role(path) == role)
+            .collect();
+
+        for index in 0..left_role.len().max(right_role.len()) {
+            pairs.push((
+                left_role.get(index).map(|path| path.to_string()),
+                right_role.get(index).map(|path| path.to_string()),
+            ));
+        }
+    }
+
+    Ok(pairs)
+}
+
+fn count_day_tests(side: &DaySide) -> Result<usize, KataError> {
+    let mut count = 0;
+    for path in day_files(&side.day_folder_path)? {
+        if side.language.file_role(&path) == FileRole::Test {
+            let content = read_day_file(&side.day_folder_path, Some(&path))?;
+            count += count_tests(side.language, &content);
+        }
+    }
+    Ok(count)
+}
+
+fn count_tests(language: Language, content: &str) -> usize {
+    content
+        .lines()
+        .map(str::trim_start)
+        .filter(|line| match language {
+            Language::Rust => line.starts_with("#[test]"),
+            Language::TS => ["it(", "test(", "Deno.test("]
+                .iter()
+                .any(|marker| line.starts_with(marker)),
+            // A day uses one of the frameworks
+            Language::CSharp => CSharpTestFramework::ALL
+                .iter()
+                .flat_map(|framework| framework.test_attributes())
+                .any(|attribute| line.starts_with(attribute)),
+        })
+        .count()
+}
+
+fn day_files(day_folder_path: &Path) -> Result<Vec<String>, KataError> {
+    Ok(collect_source_files(day_folder_path)?
+        .iter()
+        .map(|path| path.to_string_lossy().replace('\\', "/"))
+        .collect())
+}
+
+fn read_day_file(day_folder_path: &Path, relative_path: Option<&str>) -> Result<String, KataError> {
+    match relative_path {
+        Some(relative_path) => fs::read_to_string(day_folder_path.join(relative_path))
+            .map_err(|e| KataError::io(format!("Failed to read {}", relative_path), e)),
+        None => Ok(String::new()),
+    }
+}
+
+fn display_name(root: &Path, day_folder_path: &Path, relative_path: Option<&str>) -> String {
+    match relative_path {
+        Some(relative_path) => {
+            let path = day_folder_path.join(relative_path);
+            path.strip_prefix(root)
+                .unwrap_or(&path)
+                .display()
+                .to_string()
+        }
+        None => "/dev/null".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{write_template_files, Kata, TemplateOptions, TsRunner};
+
+    /// The tests `diff --stat` counts in a freshly generated day.
+    fn count_template_tests(name: &str, language: Language, options: &TemplateOptions) -> usize {
+        let day_folder_path = std::env::temp_dir().join(format!(
+            "kata_machine_compare_{}_{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(&day_folder_path).unwrap();
+        write_template_files(&day_folder_path, language, Kata::Calculator, options).unwrap();
+        let count = count_day_tests(&DaySide {
+            language,
+            day_folder_path: day_folder_path.clone(),
+        });
+        fs::remove_dir_all(&day_folder_path).unwrap();
+        count.unwrap()
+    }
+
+    #[test]
+    fn counts_the_tests_of_every_csharp_test_framework() {
+        for framework in CSharpTestFramework::ALL {
+            let options = TemplateOptions {
+                csharp_target_framework: Some("net8.0".to_string()),
+                csharp_test_framework: framework,
+                ..Default::default()
+            };
+            assert_eq!(
+                count_template_tests(framework.as_str(), Language::CSharp, &options),
+                1,
+                "{}",
+                framework.as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn counts_data_driven_csharp_tests() {
+        let content = "[TestCase(1)]\n[TestCase(2)]\npublic void A() {}\n[Theory]\n[InlineData(1)]\n[InlineData(2)]\npublic void B() {}\n[DataTestMethod]\npublic void C() {}";
+        assert_eq!(count_tests(Language::CSharp, content), 4);
+    }
+
+    #[test]
+    fn counts_the_tests_of_every_typescript_runner() {
+        for runner in TsRunner::ALL {
+            let options = TemplateOptions {
+                ts_runner: runner,
+                ..Default::default()
+            };
+            assert_eq!(
+                count_template_tests(runner.as_str(), Language::TS, &options),
+                1,
+                "{}",
+                runner.as_str()
+            );
+        }
+    }
+}
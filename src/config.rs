@@ -0,0 +1,648 @@
+use crate::{
+    console_utils, errors::KataError, history::content_hash, is_valid_edition,
+    is_valid_target_framework, is_valid_toolchain, parse_timeout, process_utils,
+    CSharpTestFramework, DayNaming, Editor, Kata, Language, RustDevDependency, TsRunner,
+};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use toml_edit::{DocumentMut, Item, Table};
+
+/// Where a config value came from. Each layer overrides the ones before it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigLayer {
+    Default,
+    /// `~/.config/kata_machine/config.toml`
+    User,
+    /// `kata_machine.toml` in the workspace root
+    Workspace,
+    /// `KATA_MACHINE_<KEY>` environment variables
+    Env,
+}
+
+impl ConfigLayer {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigLayer::Default => "default",
+            ConfigLayer::User => "user",
+            ConfigLayer::Workspace => "workspace",
+            ConfigLayer::Env => "env",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigValue {
+    Text(String),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl fmt::Display for ConfigValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigValue::Text(text) => write!(f, "{}", text),
+            ConfigValue::Bool(flag) => write!(f, "{}", flag),
+            ConfigValue::List(items) => write!(f, "{}", items.join(",")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ValueKind {
+    Text,
+    Bool,
+    /// Comma separated in environment variables and `config set`.
+    List,
+}
+
+struct ConfigKey {
+    /// `<key>` or `<table>.<key>`, as in the TOML files.
+    name: &'static str,
+    kind: ValueKind,
+    default: Option<&'static str>,
+    /// Checks a text value or a single list item.
+    is_valid: fn(&str) -> bool,
+    expected: &'static str,
+}
+
+const ANY: fn(&str) -> bool = |_| true;
+
+const KEYS: [ConfigKey; 17] = [
+    ConfigKey {
+        name: "language",
+        kind: ValueKind::Text,
+        default: Some("rust"),
+        is_valid: |value| ["rust", "ts", "cs"].contains(&value),
+        expected: "rust, ts or cs",
+    },
+    ConfigKey {
+        name: "kata",
+        kind: ValueKind::Text,
+        default: Some("calculator"),
+        is_valid: |value| Kata::get(value).is_some(),
+        expected: "calculator, dsa or blank",
+    },
+    ConfigKey {
+        name: "day_naming",
+        kind: ValueKind::Text,
+        default: Some("plain"),
+        is_valid: |value| DayNaming::get(value).is_some(),
+        expected: "plain or padded",
+    },
+    ConfigKey {
+        name: "editor",
+        kind: ValueKind::Text,
+        default: None,
+        is_valid: |value| Editor::get(value).is_some(),
+        expected: "vscode, helix, neovim or jetbrains",
+    },
+    ConfigKey {
+        name: "rust.toolchain",
+        kind: ValueKind::Text,
+        default: None,
+        is_valid: is_valid_toolchain,
+        expected: "stable, beta, nightly or a version like 1.82.0",
+    },
+    ConfigKey {
+        name: "rust.edition",
+        kind: ValueKind::Text,
+        default: Some("2021"),
+        is_valid: is_valid_edition,
+        expected: "2015, 2018, 2021 or 2024",
+    },
+    ConfigKey {
+        name: "rust.dev_dependencies",
+        kind: ValueKind::List,
+        default: None,
+        is_valid: |value| RustDevDependency::get(value).is_some(),
+        expected: "proptest, rstest or pretty_assertions",
+    },
+    ConfigKey {
+        name: "rust.no_std",
+        kind: ValueKind::Bool,
+        default: Some("false"),
+        is_valid: ANY,
+        expected: "true or false",
+    },
+    ConfigKey {
+        name: "rust.cargo_workspace",
+        kind: ValueKind::Bool,
+        default: Some("false"),
+        is_valid: ANY,
+        expected: "true or false",
+    },
+    ConfigKey {
+        name: "typescript.runner",
+        kind: ValueKind::Text,
+        default: Some("jest"),
+        is_valid: |value| TsRunner::get(value).is_some(),
+        expected: "jest, vitest, node, deno or bun",
+    },
+    ConfigKey {
+        name: "csharp.target_framework",
+        kind: ValueKind::Text,
+        default: None,
+        is_valid: is_valid_target_framework,
+        expected: "a target framework like net8.0",
+    },
+    ConfigKey {
+        name: "csharp.test_framework",
+        kind: ValueKind::Text,
+        default: Some("nunit"),
+        is_valid: |value| CSharpTestFramework::get(value).is_some(),
+        expected: "nunit, xunit or mstest",
+    },
+    ConfigKey {
+        name: "test.timeout",
+        kind: ValueKind::Text,
+        default: Some("10m"),
+        is_valid: |value| parse_timeout(value).is_some(),
+        expected: "a duration like 30s, 5m or 1h",
+    },
+    ConfigKey {
+        name: "test.memory_limit",
+        kind: ValueKind::Text,
+        default: None,
+        is_valid: |value| value.parse::<u64>().is_ok_and(|limit_mb| limit_mb > 0),
+        expected: "a number of megabytes like 512",
+    },
+    ConfigKey {
+        name: "hooks.post_generate",
+        kind: ValueKind::Text,
+        default: None,
+        is_valid: ANY,
+        expected: "a shell command",
+    },
+    ConfigKey {
+        name: "hooks.pre_test",
+        kind: ValueKind::Text,
+        default: None,
+        is_valid: ANY,
+        expected: "a shell command",
+    },
+    ConfigKey {
+        name: "hooks.post_test",
+        kind: ValueKind::Text,
+        default: None,
+        is_valid: ANY,
+        expected: "a shell command",
+    },
+];
+
+fn find_key(name: &str) -> Result<&'static ConfigKey, KataError> {
+    KEYS.iter().find(|key| key.name == name).ok_or_else(|| {
+        KataError::InvalidInput(format!(
+            "Unknown config key: {}. Run `kata_machine config list` to see the keys.",
+            name
+        ))
+    })
+}
+
+/// The config of a workspace, merged from the defaults, the user and workspace files and the environment.
+pub struct Config {
+    values: BTreeMap<&'static str, (ConfigValue, ConfigLayer)>,
+    /// Invalid values of the `rust.*`, `typescript.*` and `csharp.*` keys, which only get in the
+    /// way of the commands working on that language.
+    invalid: BTreeMap<&'static str, String>,
+}
+
+impl Config {
+    pub fn load(root: &Path) -> Result<Self, KataError> {
+        let mut values = BTreeMap::new();
+        let mut invalid = BTreeMap::new();
+        for key in &KEYS {
+            if let Some(default) = key.default {
+                values.insert(key.name, (parse_value(key, default)?, ConfigLayer::Default));
+            }
+        }
+
+        let files = [
+            (user_config_path(), ConfigLayer::User),
+            (Some(workspace_config_path(root)), ConfigLayer::Workspace),
+        ];
+        for (path, layer) in files {
+            let Some(path) = path else {
+                continue;
+            };
+            let Some(document) = read_document(&path)? else {
+                continue;
+            };
+            for (name, item) in flatten(document.as_table()) {
+                let Ok(key) = find_key(&name) else {
+                    eprintln!(
+                        "Ignoring unknown config key {} in {}.",
+                        name,
+                        path.display()
+                    );
+                    continue;
+                };
+                let value = item_value(key, item)
+                    .map_err(|message| format!("{} in {}", message, path.display()));
+                insert_value(&mut values, &mut invalid, key, value, layer)?;
+            }
+        }
+
+        for key in &KEYS {
+            if let Ok(raw) = std::env::var(env_var_name(key.name)) {
+                let value = parse_value(key, &raw)
+                    .map_err(|e| format!("{} in ${}", e, env_var_name(key.name)));
+                insert_value(&mut values, &mut invalid, key, value, ConfigLayer::Env)?;
+            }
+        }
+
+        Ok(Config { values, invalid })
+    }
+
+    /// Fails on an invalid value among the keys of the language.
+    pub fn check_language(&self, language: Language) -> Result<(), KataError> {
+        match self
+            .invalid
+            .iter()
+            .find(|(name, _)| key_language(name) == Some(language))
+        {
+            Some((_, message)) => Err(KataError::InvalidInput(message.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether `name` is one of the known keys, set or not.
+    pub fn is_key(name: &str) -> bool {
+        find_key(name).is_ok()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&(ConfigValue, ConfigLayer)> {
+        self.values.get(name)
+    }
+
+    pub fn text(&self, name: &str) -> Option<&str> {
+        match self.get(name) {
+            Some((ConfigValue::Text(text), _)) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        matches!(self.get(name), Some((ConfigValue::Bool(true), _)))
+    }
+
+    pub fn list(&self, name: &str) -> Vec<&str> {
+        match self.get(name) {
+            Some((ConfigValue::List(items), _)) => items.iter().map(String::as_str).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Every known key with its value, if it has one.
+    pub fn entries(&self) -> Vec<(&'static str, Option<&(ConfigValue, ConfigLayer)>)> {
+        KEYS.iter()
+            .map(|key| (key.name, self.values.get(key.name)))
+            .collect()
+    }
+}
+
+/// Sets the value of a layer. An invalid value fails the load, unless the key belongs to a
+/// language, in which case it is set aside for `Config::check_language`.
+fn insert_value(
+    values: &mut BTreeMap<&'static str, (ConfigValue, ConfigLayer)>,
+    invalid: &mut BTreeMap<&'static str, String>,
+    key: &ConfigKey,
+    value: Result<ConfigValue, String>,
+    layer: ConfigLayer,
+) -> Result<(), KataError> {
+    match value {
+        Ok(value) => {
+            invalid.remove(key.name);
+            values.insert(key.name, (value, layer));
+        }
+        Err(message) if key_language(key.name).is_some() => {
+            values.remove(key.name);
+            invalid.insert(key.name, message);
+        }
+        Err(message) => return Err(KataError::InvalidInput(message)),
+    }
+    Ok(())
+}
+
+/// The language whose table the key is in, such as Rust for `rust.edition`.
+fn key_language(name: &str) -> Option<Language> {
+    let (table, _) = name.split_once('.')?;
+    Language::ALL
+        .into_iter()
+        .find(|language| language.as_str() == table)
+}
+
+pub fn user_config_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("kata_machine").join("config.toml"))
+}
+
+pub fn workspace_config_path(root: &Path) -> PathBuf {
+    root.join("kata_machine.toml")
+}
+
+/// `rust.toolchain` is read from `KATA_MACHINE_RUST_TOOLCHAIN`.
+fn env_var_name(name: &str) -> String {
+    format!("KATA_MACHINE_{}", name.replace('.', "_").to_uppercase())
+}
+
+/// Runs the `hooks.*` command, if one is set, with `sh -c` in the workspace root. Each command
+/// from the workspace config has to be allowed once, as it comes with whoever shared the workspace.
+pub(crate) fn run_hook(
+    root: &Path,
+    config: &Config,
+    name: &str,
+    env: &[(&'static str, String)],
+) -> Result<(), KataError> {
+    let Some((ConfigValue::Text(hook), layer)) = config.get(name) else {
+        return Ok(());
+    };
+    if *layer == ConfigLayer::Workspace {
+        allow_workspace_hook(root, name, hook)?;
+    }
+
+    let source = match layer {
+        ConfigLayer::Default => "the defaults".to_string(),
+        ConfigLayer::User => user_config_path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default(),
+        ConfigLayer::Workspace => workspace_config_path(root).display().to_string(),
+        ConfigLayer::Env => env_var_name(name),
+    };
+    println!("Running {} from {}: {}", name, source, hook);
+
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(hook).current_dir(root);
+    for (name, value) in env {
+        command.env(name, value);
+    }
+    process_utils::run(&mut command).map(|_| ())
+}
+
+/// Lists the workspace hooks allowed so far, one hash of the workspace, key and command per line.
+/// It lives next to the user config, out of reach of the workspaces themselves.
+fn allowed_hooks_path() -> Option<PathBuf> {
+    user_config_path().map(|path| path.with_file_name("allowed_hooks"))
+}
+
+/// Asks before a workspace hook first runs, and again whenever its command changes.
+fn allow_workspace_hook(root: &Path, name: &str, hook: &str) -> Result<(), KataError> {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let entry = content_hash(format!("{}\n{}\n{}", root.display(), name, hook).as_bytes());
+    let allowed_hooks_path = allowed_hooks_path();
+    let allowed = allowed_hooks_path
+        .as_ref()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_default();
+    if allowed.lines().any(|line| line == entry) {
+        return Ok(());
+    }
+
+    let config_path = workspace_config_path(&root);
+    println!("{} sets {} to run:", config_path.display(), name);
+    println!("  {}", hook);
+    if !console_utils::confirm("Allow this hook to run in this workspace?")? {
+        return Err(KataError::InvalidInput(format!(
+            "{} in {} was not allowed to run.",
+            name,
+            config_path.display()
+        )));
+    }
+
+    if let Some(path) = allowed_hooks_path {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| KataError::io(format!("Failed to create {}", parent.display()), e))?;
+        }
+        fs::write(&path, format!("{}{}\n", allowed, entry))
+            .map_err(|e| KataError::io(format!("Failed to write {}", path.display()), e))?;
+    }
+    Ok(())
+}
+
+/// Writes the value into the config file, keeping the rest of the file and its comments as they are.
+pub fn set_value(path: &Path, name: &str, raw: &str) -> Result<(), KataError> {
+    let key = find_key(name)?;
+    let value = parse_value(key, raw)?;
+    let mut document = read_document(path)?.unwrap_or_default();
+
+    let item = match value {
+        ConfigValue::Text(text) => toml_edit::value(text),
+        ConfigValue::Bool(flag) => toml_edit::value(flag),
+        ConfigValue::List(items) => {
+            toml_edit::value(items.into_iter().collect::<toml_edit::Array>())
+        }
+    };
+    match key.name.split_once('.') {
+        Some((table, name)) => {
+            if !document.contains_table(table) {
+                document.insert(table, Item::Table(Table::new()));
+            }
+            let Some(table) = document[table].as_table_mut() else {
+                return Err(KataError::InvalidInput(format!(
+                    "{} in {} is not a table.",
+                    table,
+                    path.display()
+                )));
+            };
+            table.insert(name, item);
+        }
+        None => {
+            document.insert(key.name, item);
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| KataError::io(format!("Failed to create {}", parent.display()), e))?;
+    }
+    fs::write(path, document.to_string())
+        .map_err(|e| KataError::io(format!("Failed to write {}", path.display()), e))
+}
+
+fn read_document(path: &Path) -> Result<Option<DocumentMut>, KataError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(KataError::io(
+                format!("Failed to read {}", path.display()),
+                e,
+            ))
+        }
+    };
+
+    content
+        .parse::<DocumentMut>()
+        .map(Some)
+        .map_err(|e| KataError::InvalidInput(format!("Failed to parse {}: {}", path.display(), e)))
+}
+
+/// The values of the file by dotted name, one table deep.
+fn flatten(table: &Table) -> Vec<(String, &Item)> {
+    let mut items = Vec::new();
+    for (name, item) in table.iter() {
+        match item.as_table() {
+            Some(inner) => items.extend(
+                inner
+                    .iter()
+                    .map(|(inner_name, item)| (format!("{}.{}", name, inner_name), item)),
+            ),
+            None => items.push((name.to_string(), item)),
+        }
+    }
+    items
+}
+
+fn item_value(key: &ConfigKey, item: &Item) -> Result<ConfigValue, String> {
+    let invalid = || format!("Invalid {}, expected {}", key.name, key.expected);
+    let value = match key.kind {
+        // Numbers such as `memory_limit = 512` are kept as text like the other values
+        ValueKind::Text => ConfigValue::Text(
+            item.as_str()
+                .map(str::to_string)
+                .or_else(|| item.as_integer().map(|number| number.to_string()))
+                .ok_or_else(invalid)?,
+        ),
+        ValueKind::Bool => ConfigValue::Bool(item.as_bool().ok_or_else(invalid)?),
+        ValueKind::List => ConfigValue::List(
+            item.as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|item| item.as_str().map(str::to_string).ok_or_else(invalid))
+                .collect::<Result<_, _>>()?,
+        ),
+    };
+    check_value(key, &value).map_err(|_| invalid())?;
+    Ok(value)
+}
+
+/// Parses a value given as text, on the command line or in an environment variable.
+fn parse_value(key: &ConfigKey, raw: &str) -> Result<ConfigValue, KataError> {
+    let value = match key.kind {
+        ValueKind::Text => ConfigValue::Text(raw.to_string()),
+        ValueKind::Bool => match raw {
+            "true" | "1" => ConfigValue::Bool(true),
+            "false" | "0" => ConfigValue::Bool(false),
+            _ => ConfigValue::Text(raw.to_string()),
+        },
+        ValueKind::List => ConfigValue::List(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect(),
+        ),
+    };
+    check_value(key, &value)?;
+    Ok(value)
+}
+
+fn check_value(key: &ConfigKey, value: &ConfigValue) -> Result<(), KataError> {
+    let is_valid = match value {
+        ConfigValue::Text(text) => key.kind == ValueKind::Text && (key.is_valid)(text),
+        ConfigValue::Bool(_) => key.kind == ValueKind::Bool,
+        ConfigValue::List(items) => {
+            key.kind == ValueKind::List && items.iter().all(|item| (key.is_valid)(item))
+        }
+    };
+
+    if is_valid {
+        Ok(())
+    } else {
+        Err(KataError::InvalidInput(format!(
+            "Invalid {}: {}, expected {}",
+            key.name, value, key.expected
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text<'a>(config: &'a Config, name: &str) -> Option<(&'a str, ConfigLayer)> {
+        match config.get(name) {
+            Some((ConfigValue::Text(text), layer)) => Some((text, *layer)),
+            _ => None,
+        }
+    }
+
+    // The only test touching the environment, as the tests run in parallel
+    #[test]
+    fn layers_override_each_other() {
+        let dir = std::env::temp_dir().join(format!("kata_machine_config_{}", std::process::id()));
+        let user_dir = dir.join("xdg").join("kata_machine");
+        fs::create_dir_all(&user_dir).unwrap();
+        fs::write(
+            user_dir.join("config.toml"),
+            "kata = \"dsa\"\nday_naming = \"padded\"\n[rust]\nedition = \"2018\"\n",
+        )
+        .unwrap();
+        fs::write(
+            workspace_config_path(&dir),
+            "kata = \"blank\"\n[rust]\nedition = \"2024\"\n[csharp]\ntarget_framework = \"bogus\"\n",
+        )
+        .unwrap();
+        std::env::set_var("XDG_CONFIG_HOME", dir.join("xdg"));
+        std::env::set_var("KATA_MACHINE_RUST_EDITION", "2015");
+
+        let config = Config::load(&dir);
+        std::env::remove_var("KATA_MACHINE_RUST_EDITION");
+        std::env::remove_var("XDG_CONFIG_HOME");
+        fs::remove_dir_all(&dir).unwrap();
+        let config = config.unwrap();
+
+        assert_eq!(
+            text(&config, "language"),
+            Some(("rust", ConfigLayer::Default))
+        );
+        assert_eq!(
+            text(&config, "day_naming"),
+            Some(("padded", ConfigLayer::User))
+        );
+        assert_eq!(
+            text(&config, "kata"),
+            Some(("blank", ConfigLayer::Workspace))
+        );
+        assert_eq!(
+            text(&config, "rust.edition"),
+            Some(("2015", ConfigLayer::Env))
+        );
+
+        // An invalid value of one language only fails that language
+        assert_eq!(text(&config, "csharp.target_framework"), None);
+        assert!(config.check_language(Language::Rust).is_ok());
+        assert!(config.check_language(Language::TS).is_ok());
+        assert!(config.check_language(Language::CSharp).is_err());
+    }
+
+    #[test]
+    fn values_are_checked() {
+        let key = find_key("test.timeout").unwrap();
+        assert!(parse_value(key, "5m").is_ok());
+        assert!(parse_value(key, "soon").is_err());
+
+        let key = find_key("rust.dev_dependencies").unwrap();
+        assert_eq!(
+            parse_value(key, "proptest, rstest,").unwrap(),
+            ConfigValue::List(vec!["proptest".to_string(), "rstest".to_string()])
+        );
+        assert!(parse_value(key, "proptest,serde").is_err());
+
+        let key = find_key("rust.no_std").unwrap();
+        assert_eq!(parse_value(key, "1").unwrap(), ConfigValue::Bool(true));
+        assert!(parse_value(key, "yes").is_err());
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(find_key("rust.unknown").is_err());
+        assert!(Config::is_key("hooks.pre_test"));
+    }
+}
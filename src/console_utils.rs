@@ -0,0 +1,34 @@
+use crate::errors::KataError;
+use std::io::{self, BufRead, Write};
+
+/// Prints the message and waits for a line on stdin. Returns `false` if the user wants to quit.
+pub fn prompt(message: &str) -> Result<bool, KataError> {
+    println!("{}", message);
+    io::stdout()
+        .flush()
+        .map_err(|e| KataError::io("Failed to flush stdout", e))?;
+
+    let mut line = String::new();
+    let read = io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| KataError::io("Failed to read input", e))?;
+
+    Ok(read > 0 && !line.trim().eq_ignore_ascii_case("q"))
+}
+
+/// Asks a yes/no question, defaulting to no.
+pub fn confirm(question: &str) -> Result<bool, KataError> {
+    print!("{} [y/N] ", question);
+    io::stdout()
+        .flush()
+        .map_err(|e| KataError::io("Failed to flush stdout", e))?;
+
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| KataError::io("Failed to read input", e))?;
+
+    Ok(matches!(line.trim().to_lowercase().as_str(), "y" | "yes"))
+}
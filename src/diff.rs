@@ -0,0 +1,285 @@
+const CONTEXT_LINES: usize = 3;
+
+/// Above this many cells the longest common subsequence table is not built, and the changed
+/// lines are shown as removed then added as a whole.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Computes a line based diff of the two texts using their longest common subsequence.
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    // Only the lines between the common prefix and suffix need the table
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(old_line, new_line)| old_line == new_line)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(old_line, new_line)| old_line == new_line)
+        .count();
+    let old_middle = &old_lines[prefix..old_lines.len() - suffix];
+    let new_middle = &new_lines[prefix..new_lines.len() - suffix];
+
+    let mut lines = Vec::with_capacity(old_lines.len().max(new_lines.len()));
+    lines.extend(old_lines[..prefix].iter().map(|line| DiffLine::Same(line)));
+    lines.extend(diff_middle(old_middle, new_middle));
+    lines.extend(
+        old_lines[old_lines.len() - suffix..]
+            .iter()
+            .map(|line| DiffLine::Same(line)),
+    );
+
+    lines
+}
+
+fn diff_middle<'a>(old_lines: &[&'a str], new_lines: &[&'a str]) -> Vec<DiffLine<'a>> {
+    let (n, m) = (old_lines.len(), new_lines.len());
+    if (n + 1).saturating_mul(m + 1) > MAX_LCS_CELLS {
+        return old_lines
+            .iter()
+            .map(|line| DiffLine::Removed(line))
+            .chain(new_lines.iter().map(|line| DiffLine::Added(line)))
+            .collect();
+    }
+
+    // lcs[i][j] holds the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_lines[i] == new_lines[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old_lines[i] == new_lines[j] {
+            lines.push(DiffLine::Same(old_lines[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(DiffLine::Removed(old_lines[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new_lines[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old_lines[i..].iter().map(|line| DiffLine::Removed(line)));
+    lines.extend(new_lines[j..].iter().map(|line| DiffLine::Added(line)));
+
+    lines
+}
+
+/// Returns the number of added and removed lines between the two texts.
+pub fn diff_stat(old: &str, new: &str) -> (usize, usize) {
+    diff_lines(old, new)
+        .iter()
+        .fold((0, 0), |(added, removed), line| match line {
+            DiffLine::Added(_) => (added + 1, removed),
+            DiffLine::Removed(_) => (added, removed + 1),
+            DiffLine::Same(_) => (added, removed),
+        })
+}
+
+/// Renders the difference between the two texts in the unified diff format.
+/// Returns an empty string if the texts have the same lines.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let lines = diff_lines(old, new);
+    let changed: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, DiffLine::Same(_)))
+        .map(|(index, _)| index)
+        .collect();
+
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    // Group the changes into hunks, merging those whose context overlaps
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for index in changed {
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + CONTEXT_LINES + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut output = format!("--- {}\n+++ {}\n", old_name, new_name);
+    for (start, end) in hunks {
+        let old_start = 1 + lines[..start]
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Added(_)))
+            .count();
+        let new_start = 1 + lines[..start]
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Removed(_)))
+            .count();
+        let hunk = &lines[start..end];
+        let old_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Added(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Removed(_)))
+            .count();
+
+        // Empty ranges point at the line before them, as in GNU diff
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start - usize::from(old_count == 0),
+            old_count,
+            new_start - usize::from(new_count == 0),
+            new_count
+        ));
+        for line in hunk {
+            match line {
+                DiffLine::Same(text) => output.push_str(&format!(" {}\n", text)),
+                DiffLine::Removed(text) => output.push_str(&format!("-{}\n", text)),
+                DiffLine::Added(text) => output.push_str(&format!("+{}\n", text)),
+            }
+        }
+    }
+
+    output
+}
+
+/// Renders the difference between the two texts as two columns of the given width.
+pub fn side_by_side_diff(old: &str, new: &str, width: usize) -> String {
+    let lines = diff_lines(old, new);
+    let mut output = String::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        if let DiffLine::Same(text) = lines[index] {
+            output.push_str(&side_by_side_row(text, ' ', text, width));
+            index += 1;
+            continue;
+        }
+
+        // Pair up the removed and added lines of a block of changes
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        while index < lines.len() {
+            match lines[index] {
+                DiffLine::Removed(text) => removed.push(text),
+                DiffLine::Added(text) => added.push(text),
+                DiffLine::Same(_) => break,
+            }
+            index += 1;
+        }
+
+        for row in 0..removed.len().max(added.len()) {
+            let row = match (removed.get(row), added.get(row)) {
+                (Some(left), Some(right)) => side_by_side_row(left, '|', right, width),
+                (Some(left), None) => side_by_side_row(left, '<', "", width),
+                (None, Some(right)) => side_by_side_row("", '>', right, width),
+                (None, None) => unreachable!(),
+            };
+            output.push_str(&row);
+        }
+    }
+
+    output
+}
+
+fn side_by_side_row(left: &str, marker: char, right: &str, width: usize) -> String {
+    let left: String = left.chars().take(width).collect();
+    let right: String = right.chars().take(width).collect();
+    let row = format!("{:<width$} {} {}", left, marker, right, width = width);
+    format!("{}\n", row.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lines_of_empty_texts() {
+        assert!(diff_lines("", "").is_empty());
+        assert_eq!(
+            diff_lines("", "a\nb"),
+            [DiffLine::Added("a"), DiffLine::Added("b")]
+        );
+        assert_eq!(diff_lines("a", ""), [DiffLine::Removed("a")]);
+    }
+
+    #[test]
+    fn diff_lines_keeps_the_common_lines() {
+        assert_eq!(
+            diff_lines("a\nb\nc\nd", "a\nx\nc\nd\ne"),
+            [
+                DiffLine::Same("a"),
+                DiffLine::Removed("b"),
+                DiffLine::Added("x"),
+                DiffLine::Same("c"),
+                DiffLine::Same("d"),
+                DiffLine::Added("e"),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_lines_replaces_a_middle_too_large_for_the_table() {
+        let old: String = (0..3000).map(|i| format!("old {}\n", i)).collect();
+        let new: String = (0..3000).map(|i| format!("new {}\n", i)).collect();
+        let old = format!("first\n{}last", old);
+        let new = format!("first\n{}last", new);
+
+        let lines = diff_lines(&old, &new);
+        assert_eq!(lines.len(), 6002);
+        assert_eq!(lines[0], DiffLine::Same("first"));
+        assert_eq!(lines[1], DiffLine::Removed("old 0"));
+        assert_eq!(lines[3001], DiffLine::Added("new 0"));
+        assert_eq!(lines[6001], DiffLine::Same("last"));
+    }
+
+    #[test]
+    fn unified_diff_of_identical_texts_is_empty() {
+        assert_eq!(unified_diff("", "", "a", "b"), "");
+        assert_eq!(unified_diff("x\ny\n", "x\ny", "a", "b"), "");
+    }
+
+    #[test]
+    fn unified_diff_from_an_empty_text() {
+        assert_eq!(
+            unified_diff("", "x\ny", "a", "b"),
+            "--- a\n+++ b\n@@ -0,0 +1,2 @@\n+x\n+y\n"
+        );
+        assert_eq!(
+            unified_diff("x", "", "a", "b"),
+            "--- a\n+++ b\n@@ -1,1 +0,0 @@\n-x\n"
+        );
+    }
+
+    #[test]
+    fn unified_diff_splits_distant_changes_into_hunks() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10";
+        let new = "one\n2\n3\n4\n5\n6\n7\n8\n9\nten";
+        assert_eq!(
+            unified_diff(old, new, "a", "b"),
+            "--- a\n+++ b\n\
+             @@ -1,4 +1,4 @@\n-1\n+one\n 2\n 3\n 4\n\
+             @@ -7,4 +7,4 @@\n 7\n 8\n 9\n-10\n+ten\n"
+        );
+    }
+}
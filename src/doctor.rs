@@ -0,0 +1,513 @@
+use crate::{
+    config::Config,
+    editor,
+    errors::KataError,
+    languages::{
+        csharp::{commands::read_csharp_project_settings, solution::plan_csharp_solution},
+        rust::cargo_workspace::plan_cargo_workspace,
+    },
+    list_day_folders,
+    process_utils::run_captured,
+    Editor, Kata, Language, RustCrateOptions, Session, TsRunner,
+};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The oldest versions the generated days are known to build with.
+const MIN_RUSTC: Version = Version(1, 70, 0);
+const MIN_CARGO: Version = Version(1, 70, 0);
+const MIN_NODE: Version = Version(18, 0, 0);
+const MIN_NPM: Version = Version(9, 0, 0);
+const MIN_DOTNET_SDK: Version = Version(8, 0, 0);
+/// `node --test` running TypeScript with `--experimental-strip-types`.
+const MIN_NODE_STRIP_TYPES: Version = Version(22, 6, 0);
+/// The first rustc that knows edition 2024.
+const MIN_RUSTC_EDITION_2024: Version = Version(1, 85, 0);
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+struct Version(u32, u32, u32);
+
+impl Version {
+    /// The first `x.y.z` in the text, e.g. in `rustc 1.82.0 (f6e511eec 2024-10-15)` or `v20.18.0`.
+    fn find(text: &str) -> Option<Self> {
+        text.split(|c: char| c.is_whitespace() || c == 'v' || c == '-')
+            .find_map(|word| {
+                let mut numbers = word.split('.').map(|number| number.parse::<u32>().ok());
+                Some(Version(
+                    numbers.next()??,
+                    numbers.next()??,
+                    numbers.next().flatten().unwrap_or(0),
+                ))
+            })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    Ok,
+    Warning,
+    Problem,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Warning => "warning",
+            Status::Problem => "problem",
+        }
+    }
+}
+
+struct Check {
+    status: Status,
+    message: String,
+    fix: Option<String>,
+}
+
+/// The checks of one part of the workspace, printed under a heading.
+struct Section {
+    name: &'static str,
+    checks: Vec<Check>,
+}
+
+impl Section {
+    fn new(name: &'static str) -> Self {
+        Section {
+            name,
+            checks: Vec::new(),
+        }
+    }
+
+    fn ok(&mut self, message: String) {
+        self.checks.push(Check {
+            status: Status::Ok,
+            message,
+            fix: None,
+        });
+    }
+
+    fn warning(&mut self, message: String, fix: impl Into<Option<String>>) {
+        self.checks.push(Check {
+            status: Status::Warning,
+            message,
+            fix: fix.into(),
+        });
+    }
+
+    fn problem(&mut self, message: String, fix: impl Into<Option<String>>) {
+        self.checks.push(Check {
+            status: Status::Problem,
+            message,
+            fix: fix.into(),
+        });
+    }
+}
+
+/// A day folder and what doctor needs to know about it.
+struct Day {
+    language: Language,
+    kata: Kata,
+    path: PathBuf,
+}
+
+/// Checks the language tools, the session, the config, the editor settings and every day folder
+/// without changing anything, and prints what is wrong together with how to fix it.
+/// Fails when there is at least one problem, warnings alone pass.
+pub fn run_doctor(root: &Path) -> Result<(), KataError> {
+    let mut days = Vec::new();
+    for language in Language::ALL {
+        for kata in Kata::ALL {
+            let kata_dir = root.join(language.as_str()).join(kata.as_str());
+            days.extend(list_day_folders(&kata_dir)?.into_iter().map(|path| Day {
+                language,
+                kata,
+                path,
+            }));
+        }
+    }
+
+    let sections = [
+        check_tools(&days),
+        check_workspace(root),
+        check_days(root, &days),
+    ];
+
+    let mut problems = 0;
+    let mut warnings = 0;
+    for section in &sections {
+        println!("{}:", section.name);
+        for check in &section.checks {
+            println!("  [{}] {}", check.status.as_str(), check.message);
+            if let Some(fix) = &check.fix {
+                println!("      fix: {}", fix);
+            }
+            match check.status {
+                Status::Problem => problems += 1,
+                Status::Warning => warnings += 1,
+                Status::Ok => {}
+            }
+        }
+        println!();
+    }
+
+    if problems == 0 {
+        println!("No problems found, {} warning(s).", warnings);
+        Ok(())
+    } else {
+        Err(KataError::Other(format!(
+            "Found {} problem(s) and {} warning(s).",
+            problems, warnings
+        )))
+    }
+}
+
+fn check_tools(days: &[Day]) -> Section {
+    let mut section = Section::new("Tools");
+    let has_days = |language: Language| days.iter().any(|day| day.language == language);
+    let ts_runners: Vec<TsRunner> = days
+        .iter()
+        .filter(|day| day.language == Language::TS)
+        .map(|day| TsRunner::of_day(&day.path))
+        .collect();
+
+    let rustc = check_version(&mut section, "rustc", MIN_RUSTC, has_days(Language::Rust));
+    check_version(&mut section, "cargo", MIN_CARGO, has_days(Language::Rust));
+    let uses_edition_2024 = days.iter().any(|day| {
+        day.language == Language::Rust
+            && RustCrateOptions::of_day(&day.path).edition.as_deref() == Some("2024")
+    });
+    if let Some(rustc) = rustc.filter(|rustc| uses_edition_2024 && *rustc < MIN_RUSTC_EDITION_2024)
+    {
+        section.problem(
+            format!(
+                "rustc {} does not know edition 2024, which some Rust days use, it needs {}",
+                rustc, MIN_RUSTC_EDITION_2024
+            ),
+            "rustup update stable".to_string(),
+        );
+    }
+
+    let node = check_version(&mut section, "node", MIN_NODE, has_days(Language::TS));
+    check_version(&mut section, "npm", MIN_NPM, has_days(Language::TS));
+    if let Some(node) =
+        node.filter(|node| ts_runners.contains(&TsRunner::NodeTest) && *node < MIN_NODE_STRIP_TYPES)
+    {
+        section.problem(
+            format!(
+                "node {} cannot run the node:test days, they need {}",
+                node, MIN_NODE_STRIP_TYPES
+            ),
+            "Install Node 22.6 or later".to_string(),
+        );
+    }
+    for runner in [TsRunner::Deno, TsRunner::Bun] {
+        if ts_runners.contains(&runner) {
+            let program = runner.test_command()[0];
+            check_version(&mut section, program, Version(1, 0, 0), true);
+        }
+    }
+
+    check_dotnet(&mut section, days);
+    section
+}
+
+/// Reports the version of the tool against the minimum. A missing tool is only a problem
+/// when there are days that need it.
+fn check_version(
+    section: &mut Section,
+    program: &str,
+    minimum: Version,
+    needed: bool,
+) -> Option<Version> {
+    let version = run_captured(Command::new(program).arg("--version"))
+        .ok()
+        .filter(|(record, _)| record.status.success())
+        .and_then(|(_, output)| Version::find(&output));
+
+    match version {
+        Some(version) if version >= minimum => {
+            section.ok(format!("{} {} (needs {})", program, version, minimum))
+        }
+        Some(version) => section.problem(
+            format!("{} {} is older than {}", program, version, minimum),
+            format!("Update {} to {} or later", program, minimum),
+        ),
+        None if needed => section.problem(
+            format!("{} not found, but there are days that need it", program),
+            "kata_machine init".to_string(),
+        ),
+        None => section.warning(
+            format!("{} not found", program),
+            "kata_machine init, if you want to practice this language".to_string(),
+        ),
+    }
+    version
+}
+
+fn check_dotnet(section: &mut Section, days: &[Day]) {
+    let sdks: Vec<Version> = run_captured(Command::new("dotnet").arg("--list-sdks"))
+        .map(|(_, output)| output.lines().filter_map(Version::find).collect())
+        .unwrap_or_default();
+    let has_days = days.iter().any(|day| day.language == Language::CSharp);
+
+    match sdks
+        .iter()
+        .copied()
+        .reduce(|a, b| if b > a { b } else { a })
+    {
+        Some(newest) if newest >= MIN_DOTNET_SDK => section.ok(format!(
+            "dotnet SDKs {} (needs {})",
+            sdks.iter()
+                .map(Version::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            MIN_DOTNET_SDK
+        )),
+        Some(newest) => section.problem(
+            format!("dotnet SDK {} is older than {}", newest, MIN_DOTNET_SDK),
+            "Install the .NET 8 SDK or later".to_string(),
+        ),
+        None if has_days => section.problem(
+            "dotnet not found, but there are days that need it".to_string(),
+            "kata_machine init".to_string(),
+        ),
+        None => section.warning(
+            "dotnet not found".to_string(),
+            "kata_machine init, if you want to practice this language".to_string(),
+        ),
+    }
+
+    // netX.Y days build with an X.Y or newer SDK
+    for day in days.iter().filter(|day| day.language == Language::CSharp) {
+        let Some(target_framework) = read_csharp_project_settings(&day.path).0 else {
+            continue;
+        };
+        let Some(needed) = Version::find(target_framework.trim_start_matches("net")) else {
+            continue;
+        };
+        if !sdks.is_empty()
+            && !sdks
+                .iter()
+                .any(|sdk| (sdk.0, sdk.1) >= (needed.0, needed.1))
+        {
+            section.problem(
+                format!(
+                    "{} targets {}, which none of the installed SDKs supports",
+                    day.path.display(),
+                    target_framework
+                ),
+                format!("Install the .NET {}.{} SDK", needed.0, needed.1),
+            );
+        }
+    }
+}
+
+fn check_workspace(root: &Path) -> Section {
+    let mut section = Section::new("Workspace");
+
+    let config = Config::load(root).and_then(|config| {
+        Language::ALL
+            .into_iter()
+            .try_for_each(|language| config.check_language(language))
+    });
+    match config {
+        Ok(()) => section.ok("The config is valid".to_string()),
+        Err(error) => section.problem(
+            error.to_string(),
+            "kata_machine config set <key> <value> to correct it".to_string(),
+        ),
+    }
+
+    let session_path = root.join("session.json");
+    match Session::read_json(root) {
+        Err(KataError::SessionMissing { .. }) => section.warning(
+            "No session.json yet".to_string(),
+            "kata_machine generate".to_string(),
+        ),
+        Err(error) => section.problem(error.to_string(), None),
+        Ok(session_json) => match Session::parse(root, &session_json) {
+            Err(error) => section.problem(
+                error.to_string(),
+                format!(
+                    "Delete {} and run kata_machine generate",
+                    session_path.display()
+                ),
+            ),
+            Ok(session) => check_session(&mut section, &session, &session_path),
+        },
+    }
+
+    match editor::plan_sync(root, None, &[]) {
+        Ok(sync) if sync.is_empty() => section.ok("The editor config is up to date".to_string()),
+        Ok(sync) => section.warning(
+            format!(
+                "The editor config is out of date in {}",
+                sync.changes
+                    .iter()
+                    .map(|change| change.path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            "kata_machine editor sync".to_string(),
+        ),
+        Err(error) => section.problem(format!("The editor config is broken: {}", error), None),
+    }
+
+    match plan_cargo_workspace(root, &[], false) {
+        Ok(None) => {}
+        Ok(Some(sync)) => section.warning(
+            format!(
+                "{} is out of date: {}",
+                sync.path.display(),
+                sync.summary.join(", ")
+            ),
+            "the next kata_machine generate or clean applies it".to_string(),
+        ),
+        Err(error) => section.problem(error.to_string(), None),
+    }
+    match plan_csharp_solution(root, &[]) {
+        Ok(None) => {}
+        Ok(Some(sync)) => section.warning(
+            format!(
+                "{} is out of date: {}",
+                sync.path.display(),
+                sync.summary.join(", ")
+            ),
+            "the next kata_machine generate or clean applies it".to_string(),
+        ),
+        Err(error) => section.problem(error.to_string(), None),
+    }
+
+    section
+}
+
+fn check_session(section: &mut Section, session: &Session, session_path: &Path) {
+    let fix = format!(
+        "Delete {} and run kata_machine generate",
+        session_path.display()
+    );
+    if Language::get(session.language).is_none() {
+        section.problem(
+            format!("The session has an unknown language {}", session.language),
+            fix,
+        );
+    } else if Kata::get(session.kata).is_none() {
+        section.problem(
+            format!("The session has an unknown kata {}", session.kata),
+            fix,
+        );
+    } else if !session.day.is_dir() {
+        section.problem(
+            format!(
+                "The session points at {}, which no longer exists",
+                session.day.display()
+            ),
+            fix,
+        );
+    } else if session
+        .editor
+        .is_some_and(|name| Editor::get(name).is_none())
+    {
+        section.problem(
+            format!(
+                "The session has an unknown editor {}",
+                session.editor.unwrap_or_default()
+            ),
+            fix,
+        );
+    } else {
+        section.ok(format!("The session points at {}", session.day.display()));
+    }
+}
+
+fn check_days(root: &Path, days: &[Day]) -> Section {
+    let mut section = Section::new("Days");
+    let typescript_dir = root.join(Language::TS.as_str());
+
+    for day in days {
+        let day_name = day
+            .path
+            .strip_prefix(root)
+            .unwrap_or(&day.path)
+            .display()
+            .to_string();
+        let missing = |name: &str| !day.path.join(name).exists();
+        let clean_fix = format!(
+            "kata_machine clean --language {} --kata {} removes the days, or restore the file from another day",
+            day.language.as_str(),
+            day.kata.as_str()
+        );
+
+        let problem = match day.language {
+            Language::Rust if missing("Cargo.toml") => {
+                Some((format!("{} has no Cargo.toml", day_name), clean_fix))
+            }
+            Language::CSharp if missing("Kata.csproj") => {
+                Some((format!("{} has no Kata.csproj", day_name), clean_fix))
+            }
+            Language::TS => {
+                let runner = TsRunner::of_day(&day.path);
+                let missing_dependencies: Vec<&str> = runner
+                    .dev_dependencies()
+                    .iter()
+                    .copied()
+                    .filter(|name| {
+                        !typescript_dir
+                            .join("node_modules")
+                            .join(name)
+                            .join("package.json")
+                            .exists()
+                    })
+                    .collect();
+                if runner != TsRunner::Deno && missing("package.json") {
+                    Some((format!("{} has no package.json", day_name), clean_fix))
+                } else if !missing_dependencies.is_empty() {
+                    Some((
+                        format!(
+                            "{} is missing {} in {}",
+                            day_name,
+                            missing_dependencies.join(", "),
+                            typescript_dir.join("node_modules").display()
+                        ),
+                        "kata_machine init".to_string(),
+                    ))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+
+        match problem {
+            Some((message, fix)) => section.problem(message, fix),
+            None if day.language == Language::CSharp && missing("obj/project.assets.json") => {
+                section.warning(
+                    format!("{} was never restored", day_name),
+                    format!("dotnet restore {}", day.path.display()),
+                )
+            }
+            None => {}
+        }
+    }
+
+    let broken = section.checks.len();
+    section.checks.insert(
+        0,
+        Check {
+            status: Status::Ok,
+            message: format!("{} day folder(s), {} with findings", days.len(), broken),
+            fix: None,
+        },
+    );
+    section
+}
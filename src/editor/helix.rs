@@ -0,0 +1,41 @@
+use super::{EditorDays, MANAGED_MARKER};
+use crate::Language;
+
+pub const LANGUAGES_TOML: &str = ".helix/languages.toml";
+
+/// Workspace language settings: the Rust days for rust-analyzer, and root markers so the
+/// TypeScript and C# language servers start in the day folder instead of the workspace root.
+pub fn languages_toml(days: &EditorDays) -> Option<String> {
+    if days.all.is_empty() {
+        return None;
+    }
+
+    let mut content = format!("# {}\n", MANAGED_MARKER);
+
+    let rust_projects = days.rust_projects();
+    if !rust_projects.is_empty() {
+        content.push_str("\n[language-server.rust-analyzer.config]\nlinkedProjects = [\n");
+        for project in rust_projects {
+            content.push_str(&format!("  \"{}\",\n", project));
+        }
+        content.push_str("]\n");
+    }
+
+    for (language, name, roots) in [
+        (
+            Language::TS,
+            "typescript",
+            "\"package.json\", \"tsconfig.json\"",
+        ),
+        (Language::CSharp, "c-sharp", "\"Kata.csproj\""),
+    ] {
+        if days.all.iter().any(|day| day.language == language) {
+            content.push_str(&format!(
+                "\n[[language]]\nname = \"{}\"\nroots = [{}]\n",
+                name, roots
+            ));
+        }
+    }
+
+    Some(content)
+}
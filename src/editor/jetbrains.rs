@@ -0,0 +1,88 @@
+use super::{is_managed_file, plan_managed_file, EditorDays, FileChange, KataDay, MANAGED_MARKER};
+use crate::errors::KataError;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const RUN_CONFIGURATIONS: &str = ".idea/runConfigurations";
+const FILE_PREFIX: &str = "kata_";
+
+/// One shell run configuration per kata, which every JetBrains IDE can run.
+pub fn plan(root: &Path, days: &EditorDays) -> Result<Vec<FileChange>, KataError> {
+    let dir = root.join(RUN_CONFIGURATIONS);
+    let mut changes = Vec::new();
+    let mut wanted = Vec::new();
+
+    for day in &days.latest {
+        let path = dir.join(format!(
+            "{}{}_{}.xml",
+            FILE_PREFIX,
+            day.language.as_str(),
+            day.kata.as_str()
+        ));
+        changes.extend(plan_managed_file(&path, Some(run_configuration(day)))?);
+        wanted.push(path);
+    }
+    for path in managed_files(root) {
+        if !wanted.contains(&path) {
+            changes.extend(plan_managed_file(&path, None)?);
+        }
+    }
+
+    Ok(changes)
+}
+
+pub fn managed_files(root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(root.join(RUN_CONFIGURATIONS)) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(FILE_PREFIX))
+                && is_managed_file(path)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn run_configuration(day: &KataDay) -> String {
+    format!(
+        r#"<!-- {} -->
+<component name="ProjectRunConfigurationManager">
+  <configuration default="false" name="kata: test {}" type="ShConfigurationType">
+    <option name="SCRIPT_TEXT" value="{}" />
+    <option name="INDEPENDENT_SCRIPT_PATH" value="true" />
+    <option name="SCRIPT_PATH" value="" />
+    <option name="SCRIPT_OPTIONS" value="" />
+    <option name="INDEPENDENT_SCRIPT_WORKING_DIRECTORY" value="false" />
+    <option name="SCRIPT_WORKING_DIRECTORY" value="$PROJECT_DIR$/{}" />
+    <option name="INDEPENDENT_INTERPRETER_PATH" value="true" />
+    <option name="INTERPRETER_PATH" value="/bin/sh" />
+    <option name="INTERPRETER_OPTIONS" value="" />
+    <option name="EXECUTE_IN_TERMINAL" value="true" />
+    <option name="EXECUTE_SCRIPT_FILE" value="false" />
+    <envs />
+    <method v="2" />
+  </configuration>
+</component>
+"#,
+        escape_xml(MANAGED_MARKER),
+        escape_xml(&day.name()),
+        escape_xml(&day.test_command()),
+        escape_xml(&day.relative_path)
+    )
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
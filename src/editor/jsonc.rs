@@ -0,0 +1,323 @@
+use serde::Serialize;
+use serde_json::{ser::PrettyFormatter, Serializer, Value};
+
+pub const DEFAULT_INDENT: &str = "    ";
+
+/// Blanks out comments and trailing commas, keeping every other byte at the same offset.
+pub fn strip_jsonc(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut stripped = bytes.to_vec();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => i = skip_string(bytes, i),
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    stripped[i] = b' ';
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = text[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + end + 4);
+                for byte in &mut stripped[i..end] {
+                    if *byte != b'\n' {
+                        *byte = b' ';
+                    }
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+
+    // Trailing commas, now that the comments between them and the bracket are gone
+    let mut last_comma = None;
+    let mut i = 0;
+    while i < stripped.len() {
+        match stripped[i] {
+            b'"' => {
+                last_comma = None;
+                i = skip_string(&stripped, i);
+                continue;
+            }
+            b',' => last_comma = Some(i),
+            b'}' | b']' => {
+                if let Some(comma) = last_comma.take() {
+                    stripped[comma] = b' ';
+                }
+            }
+            byte if byte.is_ascii_whitespace() => {}
+            _ => last_comma = None,
+        }
+        i += 1;
+    }
+
+    String::from_utf8(stripped).unwrap_or_default()
+}
+
+/// Replaces the value of a top-level key, or adds the key, leaving the rest of the text untouched.
+pub fn set_top_level_value(text: &str, key: &str, values: &[Value]) -> String {
+    let stripped = strip_jsonc(text);
+    let bytes = stripped.as_bytes();
+
+    if let Some((start, end)) = top_level_value_span(bytes, key) {
+        let indent = line_indent(text, start);
+        return format!(
+            "{}{}{}",
+            &text[..start],
+            format_array(values, &indent),
+            &text[end..]
+        );
+    }
+
+    let Some(close) = stripped.rfind('}') else {
+        return text.to_string();
+    };
+    let Some(last) = stripped[..close].rfind(|c: char| !c.is_ascii_whitespace()) else {
+        return text.to_string();
+    };
+
+    if bytes[last] == b'{' {
+        return format!(
+            "{}\n{}\"{}\": {}\n{}",
+            &text[..=last],
+            DEFAULT_INDENT,
+            key,
+            format_array(values, DEFAULT_INDENT),
+            &text[close..]
+        );
+    }
+
+    let indent = match line_indent(text, last) {
+        indent if indent.is_empty() => DEFAULT_INDENT.to_string(),
+        indent => indent,
+    };
+    format!(
+        "{},\n{}\"{}\": {}{}",
+        &text[..=last],
+        indent,
+        key,
+        format_array(values, &indent),
+        &text[last + 1..]
+    )
+}
+
+fn top_level_value_span(bytes: &[u8], key: &str) -> Option<(usize, usize)> {
+    let mut i = bytes.iter().position(|&byte| byte == b'{')? + 1;
+
+    loop {
+        i = skip_whitespace(bytes, i);
+        match bytes.get(i)? {
+            b'}' => return None,
+            b',' => {
+                i += 1;
+                continue;
+            }
+            b'"' => {}
+            _ => return None,
+        }
+
+        let key_end = skip_string(bytes, i);
+        let name: String = serde_json::from_slice(&bytes[i..key_end]).ok()?;
+        i = skip_whitespace(bytes, key_end);
+        if bytes.get(i) != Some(&b':') {
+            return None;
+        }
+        let value_start = skip_whitespace(bytes, i + 1);
+        let value_end = skip_value(bytes, value_start);
+        if name == key {
+            return Some((value_start, value_end));
+        }
+        i = value_end;
+    }
+}
+
+fn skip_whitespace(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+/// Returns the offset just past the string starting at `start`.
+fn skip_string(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn skip_value(bytes: &[u8], start: usize) -> usize {
+    match bytes.get(start) {
+        Some(b'"') => skip_string(bytes, start),
+        Some(b'[') | Some(b'{') => {
+            let mut depth = 0;
+            let mut i = start;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'"' => {
+                        i = skip_string(bytes, i);
+                        continue;
+                    }
+                    b'[' | b'{' => depth += 1,
+                    b']' | b'}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return i + 1;
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            bytes.len()
+        }
+        _ => {
+            let mut i = start;
+            while i < bytes.len() && !b",}] \t\r\n".contains(&bytes[i]) {
+                i += 1;
+            }
+            i
+        }
+    }
+}
+
+fn line_indent(text: &str, offset: usize) -> String {
+    let line_start = text[..offset].rfind('\n').map_or(0, |newline| newline + 1);
+    text[line_start..]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect()
+}
+
+/// Formats the array with one item per line, objects pretty-printed with the same indent unit.
+pub fn format_array(values: &[Value], indent: &str) -> String {
+    if values.is_empty() {
+        return String::from("[]");
+    }
+
+    let unit = if indent.is_empty() {
+        DEFAULT_INDENT
+    } else {
+        indent
+    };
+    let item_indent = format!("{}{}", indent, unit);
+    let items: Vec<String> = values
+        .iter()
+        .map(|value| {
+            let mut pretty = Vec::new();
+            let formatter = PrettyFormatter::with_indent(unit.as_bytes());
+            let _ = value.serialize(&mut Serializer::with_formatter(&mut pretty, formatter));
+            let pretty =
+                String::from_utf8_lossy(&pretty).replace('\n', &format!("\n{}", item_indent));
+            format!("{}{}", item_indent, pretty)
+        })
+        .collect();
+    format!("[\n{}\n{}]", items.join(",\n"), indent)
+}
+
+/// A new document holding the given top-level keys.
+pub fn new_document(entries: &[(&str, Value)]) -> String {
+    let members: Vec<String> = entries
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::Array(values) => format_array(values, DEFAULT_INDENT),
+                value => value.to_string(),
+            };
+            format!("{}\"{}\": {}", DEFAULT_INDENT, key, value)
+        })
+        .collect();
+    format!("{{\n{}\n}}\n", members.join(",\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(text: &str) -> Value {
+        serde_json::from_str(&strip_jsonc(text)).unwrap()
+    }
+
+    #[test]
+    fn strip_jsonc_keeps_offsets() {
+        let text = "{\n  // line\n  \"a\": 1, /* block\n  */ \"b\": 2,\n}";
+        let stripped = strip_jsonc(text);
+        assert_eq!(stripped.len(), text.len());
+        assert_eq!(stripped.find("\"b\""), text.find("\"b\""));
+    }
+
+    #[test]
+    fn strip_jsonc_removes_comments_and_trailing_commas() {
+        let text = r#"{
+    // a line comment
+    "a": [1, 2, /* inline */ 3,],
+    /* a block
+       comment */
+    "b": { "c": true, },
+}"#;
+        assert_eq!(parse(text), json!({ "a": [1, 2, 3], "b": { "c": true } }));
+    }
+
+    #[test]
+    fn strip_jsonc_keeps_comment_markers_inside_strings() {
+        let text = r#"{ "url": "https://example.com", "glob": "/*.rs", "escaped": "\"//\"", }"#;
+        assert_eq!(
+            parse(text),
+            json!({ "url": "https://example.com", "glob": "/*.rs", "escaped": "\"//\"" })
+        );
+    }
+
+    #[test]
+    fn strip_jsonc_keeps_commas_inside_strings() {
+        let text = r#"{ "a": "x,]" }"#;
+        assert_eq!(parse(text), json!({ "a": "x,]" }));
+    }
+
+    #[test]
+    fn set_top_level_value_replaces_only_the_value() {
+        let text = r#"{
+    // keep me
+    "before": "//not a comment",
+    "files": [1],
+    "nested": { "files": [2] },
+}"#;
+        let updated = set_top_level_value(text, "files", &[json!(3)]);
+        assert!(updated.contains("// keep me"));
+        assert_eq!(
+            parse(&updated),
+            json!({ "before": "//not a comment", "files": [3], "nested": { "files": [2] } })
+        );
+    }
+
+    #[test]
+    fn set_top_level_value_adds_a_missing_key() {
+        let text = "{\n    \"a\": 1, // last\n}\n";
+        let updated = set_top_level_value(text, "b", &[json!("x")]);
+        assert!(updated.contains("// last"));
+        assert_eq!(parse(&updated), json!({ "a": 1, "b": ["x"] }));
+    }
+
+    #[test]
+    fn set_top_level_value_fills_an_empty_object() {
+        let updated = set_top_level_value("{}", "a", &[]);
+        assert_eq!(parse(&updated), json!({ "a": [] }));
+    }
+
+    #[test]
+    fn set_top_level_value_ignores_commented_out_keys() {
+        let text = "{\n    // \"a\": [1],\n    \"b\": 2\n}";
+        let updated = set_top_level_value(text, "a", &[json!(5)]);
+        assert!(updated.contains("// \"a\": [1],"));
+        assert_eq!(parse(&updated), json!({ "a": [5], "b": 2 }));
+    }
+}
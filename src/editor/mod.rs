@@ -0,0 +1,331 @@
+mod helix;
+mod jetbrains;
+mod jsonc;
+mod neovim;
+mod vscode;
+
+use crate::{
+    errors::KataError, languages::rust::cargo_workspace::is_cargo_workspace, list_day_folders,
+    Kata, Language, TsRunner,
+};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Marks the files kata_machine generates as a whole. Files without it are never touched.
+const MANAGED_MARKER: &str =
+    "Managed by kata_machine, `kata_machine editor sync` overwrites changes to this file.";
+
+/// The entries kata_machine added to editor config files, by file and key.
+/// Only these are ever changed or removed, everything else in the files belongs to the user.
+type OwnedEntries = BTreeMap<String, BTreeMap<String, Vec<String>>>;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Editor {
+    VsCode,
+    Helix,
+    Neovim,
+    JetBrains,
+}
+
+impl Editor {
+    const ALL: [Editor; 4] = [
+        Editor::VsCode,
+        Editor::Helix,
+        Editor::Neovim,
+        Editor::JetBrains,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Editor::VsCode => "vscode",
+            Editor::Helix => "helix",
+            Editor::Neovim => "neovim",
+            Editor::JetBrains => "jetbrains",
+        }
+    }
+
+    pub fn get(s: &str) -> Option<Self> {
+        match s {
+            "vscode" => Some(Editor::VsCode),
+            "helix" => Some(Editor::Helix),
+            "neovim" => Some(Editor::Neovim),
+            "jetbrains" => Some(Editor::JetBrains),
+            _ => None,
+        }
+    }
+
+    /// Whether the workspace already holds config for this editor.
+    fn is_configured(&self, root: &Path) -> bool {
+        match self {
+            Editor::VsCode => root.join(".vscode").exists(),
+            Editor::Helix => is_managed_file(&root.join(helix::LANGUAGES_TOML)),
+            Editor::Neovim => is_managed_file(&root.join(neovim::NVIM_LUA)),
+            Editor::JetBrains => !jetbrains::managed_files(root).is_empty(),
+        }
+    }
+}
+
+/// A day folder, with its path relative to the workspace root using `/` separators.
+pub struct KataDay {
+    pub language: Language,
+    pub kata: Kata,
+    pub relative_path: String,
+    /// The test runner of a TypeScript day.
+    pub ts_runner: TsRunner,
+}
+
+impl KataDay {
+    /// `<language>/<kata>`, which names the run configurations of the kata.
+    fn name(&self) -> String {
+        format!("{}/{}", self.language.as_str(), self.kata.as_str())
+    }
+
+    fn test_command(&self) -> String {
+        match self.language {
+            Language::Rust => "cargo test".to_string(),
+            Language::TS => self.ts_runner.test_command().join(" "),
+            Language::CSharp => "dotnet test".to_string(),
+        }
+    }
+}
+
+/// The days to wire up: every day for the language servers, the latest day of
+/// every kata for the run and debug configurations.
+pub struct EditorDays {
+    pub all: Vec<KataDay>,
+    pub latest: Vec<KataDay>,
+    /// Whether the Rust days are members of the `rust/Cargo.toml` workspace.
+    cargo_workspace: bool,
+}
+
+impl EditorDays {
+    fn load(root: &Path, ignored_days: &[PathBuf]) -> Result<Self, KataError> {
+        let mut all = Vec::new();
+        let mut latest = Vec::new();
+
+        for language in Language::ALL {
+            for kata in Kata::ALL {
+                let kata_dir = root.join(language.as_str()).join(kata.as_str());
+                let days: Vec<KataDay> = list_day_folders(&kata_dir)?
+                    .into_iter()
+                    .filter(|path| !ignored_days.contains(path))
+                    .map(|path| KataDay {
+                        language,
+                        kata,
+                        relative_path: path
+                            .strip_prefix(root)
+                            .unwrap_or(&path)
+                            .to_string_lossy()
+                            .replace('\\', "/"),
+                        ts_runner: match language {
+                            Language::TS => TsRunner::of_day(&path),
+                            _ => TsRunner::default(),
+                        },
+                    })
+                    .collect();
+
+                if let Some(day) = days.last() {
+                    latest.push(KataDay {
+                        language,
+                        kata,
+                        relative_path: day.relative_path.clone(),
+                        ts_runner: day.ts_runner,
+                    });
+                }
+                all.extend(days);
+            }
+        }
+
+        Ok(EditorDays {
+            all,
+            latest,
+            cargo_workspace: is_cargo_workspace(root),
+        })
+    }
+
+    /// The Cargo.toml files for rust-analyzer, which is just the workspace when there is one.
+    fn rust_projects(&self) -> Vec<String> {
+        let has_rust_days = self.all.iter().any(|day| day.language == Language::Rust);
+        if self.cargo_workspace && has_rust_days {
+            return vec![format!("{}/Cargo.toml", Language::Rust.as_str())];
+        }
+
+        self.all
+            .iter()
+            .filter(|day| day.language == Language::Rust)
+            .map(|day| format!("{}/Cargo.toml", day.relative_path))
+            .collect()
+    }
+}
+
+pub enum FileContent {
+    Write(String),
+    Remove,
+}
+
+/// The change to one editor config file, with a line per added (+), updated (~) or removed (-) entry.
+pub struct FileChange {
+    pub path: PathBuf,
+    pub summary: Vec<String>,
+    content: FileContent,
+}
+
+/// The changes needed to bring the editor config in line with the day folders.
+pub struct EditorSync {
+    pub changes: Vec<FileChange>,
+    owned: OwnedEntries,
+    owned_changed: bool,
+}
+
+impl EditorSync {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Every file the sync may write, including the record of owned entries.
+    pub fn paths(&self, root: &Path) -> Vec<PathBuf> {
+        self.changes
+            .iter()
+            .map(|change| change.path.clone())
+            .chain([owned_entries_path(root)])
+            .collect()
+    }
+
+    pub fn apply(&self, root: &Path) -> Result<(), KataError> {
+        for change in &self.changes {
+            match &change.content {
+                FileContent::Write(content) => {
+                    if let Some(parent) = change.path.parent() {
+                        fs::create_dir_all(parent).map_err(|e| {
+                            KataError::io(format!("Failed to create {}", parent.display()), e)
+                        })?;
+                    }
+                    fs::write(&change.path, content).map_err(|e| {
+                        KataError::io(format!("Failed to write {}", change.path.display()), e)
+                    })?;
+                }
+                FileContent::Remove => fs::remove_file(&change.path).map_err(|e| {
+                    KataError::io(format!("Failed to remove {}", change.path.display()), e)
+                })?,
+            }
+        }
+
+        if self.owned_changed {
+            let owned_json = serde_json::to_string_pretty(&self.owned)
+                .map_err(|e| KataError::json("Failed to serialize the editor entries", e))?;
+            fs::create_dir_all(root.join(".kata"))
+                .map_err(|e| KataError::io("Failed to create the .kata directory", e))?;
+            fs::write(owned_entries_path(root), owned_json)
+                .map_err(|e| KataError::io("Failed to write the editor entries", e))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Brings the config of the editor, and of every editor already configured, in line with the days.
+pub fn sync(root: &Path, editor: Option<Editor>) -> Result<EditorSync, KataError> {
+    let sync = plan_sync(root, editor, &[])?;
+    sync.apply(root)?;
+    Ok(sync)
+}
+
+/// Works out the editor changes, treating the `ignored_days` as already removed.
+pub fn plan_sync(
+    root: &Path,
+    editor: Option<Editor>,
+    ignored_days: &[PathBuf],
+) -> Result<EditorSync, KataError> {
+    let days = EditorDays::load(root, ignored_days)?;
+    let mut owned = load_owned_entries(root)?;
+    let previously_owned = owned.clone();
+    let mut changes = Vec::new();
+
+    for candidate in Editor::ALL {
+        if editor != Some(candidate) && !candidate.is_configured(root) {
+            continue;
+        }
+
+        match candidate {
+            Editor::VsCode => changes.extend(vscode::plan(root, &days, &mut owned)?),
+            Editor::Helix => changes.extend(plan_managed_file(
+                &root.join(helix::LANGUAGES_TOML),
+                helix::languages_toml(&days),
+            )?),
+            Editor::Neovim => changes.extend(plan_managed_file(
+                &root.join(neovim::NVIM_LUA),
+                neovim::nvim_lua(&days),
+            )?),
+            Editor::JetBrains => changes.extend(jetbrains::plan(root, &days)?),
+        }
+    }
+
+    let owned_changed = owned != previously_owned;
+    Ok(EditorSync {
+        changes,
+        owned,
+        owned_changed,
+    })
+}
+
+fn owned_entries_path(root: &Path) -> PathBuf {
+    root.join(".kata").join("editor.json")
+}
+
+fn load_owned_entries(root: &Path) -> Result<OwnedEntries, KataError> {
+    match fs::read_to_string(owned_entries_path(root)) {
+        Ok(owned_json) => serde_json::from_str(&owned_json)
+            .map_err(|e| KataError::json("Failed to parse the editor entries", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(OwnedEntries::new()),
+        Err(e) => Err(KataError::io("Failed to read the editor entries", e)),
+    }
+}
+
+fn is_managed_file(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .map(|content| content.contains(MANAGED_MARKER))
+        .unwrap_or(false)
+}
+
+/// Plans a file kata_machine owns as a whole: written when there is content, removed when there
+/// is none left. A file of the same name written by the user is left alone.
+fn plan_managed_file(
+    path: &Path,
+    content: Option<String>,
+) -> Result<Option<FileChange>, KataError> {
+    let existing = match fs::read_to_string(path) {
+        Ok(existing) => Some(existing),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            return Err(KataError::io(
+                format!("Failed to read {}", path.display()),
+                e,
+            ))
+        }
+    };
+    let name = path.file_name().unwrap().to_string_lossy().to_string();
+
+    let change = match (existing, content) {
+        (Some(existing), _) if !existing.contains(MANAGED_MARKER) => {
+            eprintln!(
+                "Skipping {}, it was not generated by kata_machine.",
+                path.display()
+            );
+            None
+        }
+        (Some(existing), Some(content)) if existing == content => None,
+        (Some(_), Some(content)) => Some((format!("~ {}", name), FileContent::Write(content))),
+        (None, Some(content)) => Some((format!("+ {}", name), FileContent::Write(content))),
+        (Some(_), None) => Some((format!("- {}", name), FileContent::Remove)),
+        (None, None) => None,
+    };
+
+    Ok(change.map(|(summary, content)| FileChange {
+        path: path.to_path_buf(),
+        summary: vec![summary],
+        content,
+    }))
+}
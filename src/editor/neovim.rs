@@ -0,0 +1,66 @@
+use super::{EditorDays, MANAGED_MARKER};
+
+pub const NVIM_LUA: &str = ".nvim.lua";
+
+/// A project-local config, loaded when `exrc` is on, with a `:KataTest [kata]` command
+/// and the Rust days for rust-analyzer.
+pub fn nvim_lua(days: &EditorDays) -> Option<String> {
+    if days.latest.is_empty() {
+        return None;
+    }
+
+    let mut content = format!(
+        "-- {}\n-- Neovim loads this file when `vim.o.exrc = true` is set and the file is trusted (:trust).\n\nlocal katas = {{\n",
+        MANAGED_MARKER
+    );
+    for day in &days.latest {
+        content.push_str(&format!(
+            "  {{ name = \"{}\", dir = \"{}\", test = \"{}\" }},\n",
+            day.name(),
+            day.relative_path,
+            day.test_command()
+        ));
+    }
+    content.push_str(
+        r#"}
+
+-- Without an argument this runs the tests of the current session, like `kata_machine test`
+vim.api.nvim_create_user_command("KataTest", function(opts)
+  local command = "kata_machine test"
+  for _, kata in ipairs(katas) do
+    if kata.name == opts.args then
+      command = "cd " .. vim.fn.shellescape(kata.dir) .. " && " .. kata.test
+    end
+  end
+  vim.cmd("botright split | terminal " .. command)
+end, {
+  nargs = "?",
+  complete = function()
+    return vim.tbl_map(function(kata)
+      return kata.name
+    end, katas)
+  end,
+})
+"#,
+    );
+
+    let rust_projects = days.rust_projects();
+    if !rust_projects.is_empty() {
+        let projects: Vec<String> = rust_projects
+            .iter()
+            .map(|project| format!("\"{}\"", project))
+            .collect();
+        content.push_str(&format!(
+            r#"
+if vim.lsp.config then
+  vim.lsp.config("rust_analyzer", {{
+    settings = {{ ["rust-analyzer"] = {{ linkedProjects = {{ {} }} }} }},
+  }})
+end
+"#,
+            projects.join(", ")
+        ));
+    }
+
+    Some(content)
+}
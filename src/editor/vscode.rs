@@ -0,0 +1,266 @@
+use super::{jsonc, EditorDays, FileChange, FileContent, KataDay, OwnedEntries};
+use crate::{errors::KataError, Kata, Language, TsRunner};
+use serde_json::{json, Value};
+use std::{fs, path::Path};
+
+const SETTINGS: &str = ".vscode/settings.json";
+const TASKS: &str = ".vscode/tasks.json";
+const LAUNCH: &str = ".vscode/launch.json";
+const LINKED_PROJECTS: &str = "rust-analyzer.linkedProjects";
+
+/// A top-level array in a VS Code config file that holds entries kata_machine owns.
+struct ManagedList {
+    file: &'static str,
+    key: &'static str,
+    /// The field naming an entry, or `None` when the entries are plain strings.
+    id_field: Option<&'static str>,
+    /// The other top-level keys of a newly created file.
+    preamble: Vec<(&'static str, Value)>,
+    wanted: Vec<Value>,
+}
+
+impl ManagedList {
+    fn id(&self, entry: &Value) -> Option<String> {
+        match self.id_field {
+            Some(field) => entry.get(field)?.as_str().map(str::to_string),
+            None => entry.as_str().map(str::to_string),
+        }
+    }
+}
+
+pub fn plan(
+    root: &Path,
+    days: &EditorDays,
+    owned: &mut OwnedEntries,
+) -> Result<Vec<FileChange>, KataError> {
+    let lists = [
+        ManagedList {
+            file: SETTINGS,
+            key: LINKED_PROJECTS,
+            id_field: None,
+            preamble: Vec::new(),
+            wanted: days
+                .rust_projects()
+                .into_iter()
+                .map(Value::String)
+                .collect(),
+        },
+        ManagedList {
+            file: TASKS,
+            key: "tasks",
+            id_field: Some("label"),
+            preamble: vec![("version", json!("2.0.0"))],
+            wanted: days.latest.iter().map(test_task).collect(),
+        },
+        ManagedList {
+            file: LAUNCH,
+            key: "configurations",
+            id_field: Some("name"),
+            preamble: vec![("version", json!("0.2.0"))],
+            wanted: days.latest.iter().map(debug_configuration).collect(),
+        },
+    ];
+
+    let mut changes = Vec::new();
+    for list in &lists {
+        changes.extend(plan_list(root, list, owned)?);
+    }
+    Ok(changes)
+}
+
+fn plan_list(
+    root: &Path,
+    list: &ManagedList,
+    owned: &mut OwnedEntries,
+) -> Result<Option<FileChange>, KataError> {
+    let path = root.join(list.file);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => Some(text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(KataError::io(format!("Failed to read {}", list.file), e)),
+    };
+    let document: Value = match &text {
+        Some(text) => serde_json::from_str(&jsonc::strip_jsonc(text))
+            .map_err(|e| KataError::json(format!("Failed to parse {} as JSONC", list.file), e))?,
+        None => Value::Object(Default::default()),
+    };
+    let current = document
+        .get(list.key)
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+
+    let previously_owned = match owned.get(list.file).and_then(|keys| keys.get(list.key)) {
+        Some(entries) => entries.clone(),
+        // Settings written before ownership was recorded: adopt the linked projects that look like ours
+        None if list.id_field.is_none() => current
+            .iter()
+            .filter_map(|entry| list.id(entry))
+            .filter(|entry| is_day_project(entry))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let mut entries = Vec::new();
+    let mut now_owned: Vec<String> = Vec::new();
+    let mut summary = Vec::new();
+    for entry in &current {
+        match list.id(entry) {
+            Some(id) if previously_owned.contains(&id) => {
+                let wanted = list
+                    .wanted
+                    .iter()
+                    .find(|wanted| list.id(wanted).as_ref() == Some(&id));
+                match wanted {
+                    Some(wanted) if !now_owned.contains(&id) => {
+                        if wanted != entry {
+                            summary.push(format!("~ {}", id));
+                        }
+                        entries.push(wanted.clone());
+                        now_owned.push(id);
+                    }
+                    _ => summary.push(format!("- {}", id)),
+                }
+            }
+            _ => entries.push(entry.clone()),
+        }
+    }
+    for wanted in &list.wanted {
+        let Some(id) = list.id(wanted) else {
+            continue;
+        };
+        if !entries
+            .iter()
+            .any(|entry| list.id(entry).as_ref() == Some(&id))
+        {
+            summary.push(format!("+ {}", id));
+            entries.push(wanted.clone());
+            now_owned.push(id);
+        }
+    }
+
+    if text.is_some() || !summary.is_empty() {
+        owned
+            .entry(list.file.to_string())
+            .or_default()
+            .insert(list.key.to_string(), now_owned);
+    }
+    if summary.is_empty() {
+        return Ok(None);
+    }
+
+    let content = match &text {
+        Some(text) => jsonc::set_top_level_value(text, list.key, &entries),
+        None => {
+            let mut members = list.preamble.clone();
+            members.push((list.key, Value::Array(entries)));
+            jsonc::new_document(&members)
+        }
+    };
+
+    Ok(Some(FileChange {
+        path,
+        summary,
+        content: FileContent::Write(content),
+    }))
+}
+
+/// Whether the entry has the shape kata_machine uses, `rust/<kata>/day<n>/Cargo.toml`.
+fn is_day_project(entry: &str) -> bool {
+    match entry.split('/').collect::<Vec<_>>().as_slice() {
+        [language, kata, day, "Cargo.toml"] => {
+            *language == Language::Rust.as_str()
+                && Kata::get(kata).is_some()
+                && day
+                    .strip_prefix("day")
+                    .is_some_and(|number| number.parse::<u32>().is_ok())
+        }
+        _ => false,
+    }
+}
+
+fn test_task(day: &KataDay) -> Value {
+    let problem_matcher = match day.language {
+        Language::Rust => "$rustc",
+        Language::TS => "$tsc",
+        Language::CSharp => "$msCompile",
+    };
+
+    json!({
+        "label": format!("kata: test {}", day.name()),
+        "type": "shell",
+        "command": day.test_command(),
+        "options": { "cwd": format!("${{workspaceFolder}}/{}", day.relative_path) },
+        "group": "test",
+        "problemMatcher": problem_matcher,
+    })
+}
+
+fn debug_configuration(day: &KataDay) -> Value {
+    let name = format!("kata: debug {}", day.name());
+    let cwd = format!("${{workspaceFolder}}/{}", day.relative_path);
+
+    match day.language {
+        // Needs the CodeLLDB extension
+        Language::Rust => json!({
+            "name": name,
+            "type": "lldb",
+            "request": "launch",
+            "cargo": {
+                "args": [
+                    "test",
+                    "--no-run",
+                    "--manifest-path",
+                    format!("{}/Cargo.toml", cwd),
+                ],
+            },
+            "cwd": cwd,
+        }),
+        Language::TS => match day.ts_runner {
+            // Needs the Bun extension, and debugs the test file open in the editor
+            TsRunner::Bun => json!({
+                "name": name,
+                "type": "bun",
+                "request": "launch",
+                "runtime": "bun",
+                "runtimeArgs": ["test"],
+                "program": "${file}",
+                "cwd": cwd,
+            }),
+            TsRunner::Deno => json!({
+                "name": name,
+                "type": "node",
+                "request": "launch",
+                "runtimeExecutable": "deno",
+                "runtimeArgs": ["test", "--inspect-wait"],
+                "attachSimplePort": 9229,
+                "cwd": cwd,
+                "console": "integratedTerminal",
+            }),
+            // Run the tests in one process, which the debugger attaches to
+            runner => {
+                let runtime_args = match runner {
+                    TsRunner::Jest => json!(["test", "--", "--runInBand"]),
+                    TsRunner::Vitest => json!(["test", "--", "--no-file-parallelism"]),
+                    _ => json!(["test"]),
+                };
+                json!({
+                    "name": name,
+                    "type": "node",
+                    "request": "launch",
+                    "runtimeExecutable": "npm",
+                    "runtimeArgs": runtime_args,
+                    "cwd": cwd,
+                    "console": "integratedTerminal",
+                })
+            }
+        },
+        // Start the tests with `VSTEST_HOST_DEBUG=1 dotnet test` and attach to the process it prints
+        Language::CSharp => json!({
+            "name": name,
+            "type": "coreclr",
+            "request": "attach",
+            "processId": "${command:pickProcess}",
+        }),
+    }
+}
@@ -0,0 +1,252 @@
+use serde_json::json;
+use std::{error::Error, fmt, io, path::PathBuf, time::Duration};
+
+#[derive(Debug)]
+pub enum KataError {
+    /// A language toolchain binary (cargo, npm, dotnet, ...) could not be started.
+    ToolMissing {
+        tool: String,
+        source: io::Error,
+    },
+    /// No session.json exists yet, so there is no most recent day.
+    SessionMissing {
+        path: PathBuf,
+    },
+    SessionCorrupt {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    /// The kata folder has no day folder yet.
+    DayMissing {
+        kata_dir: PathBuf,
+    },
+    GenerationFailed {
+        step: String,
+        source: Box<KataError>,
+    },
+    TestsFailed {
+        passed: u32,
+        failed: u32,
+    },
+    /// The tests were killed for running longer than the timeout.
+    TestsTimedOut {
+        timeout: Duration,
+    },
+    /// The tests were killed for using more memory than the limit.
+    TestsOutOfMemory {
+        limit_mb: u64,
+    },
+    /// An external command ran but exited with a non-zero status.
+    CommandFailed {
+        command_line: String,
+        working_dir: Option<PathBuf>,
+        status: Option<i32>,
+        duration: Duration,
+    },
+    Io {
+        context: String,
+        source: io::Error,
+    },
+    Json {
+        context: String,
+        source: serde_json::Error,
+    },
+    InvalidInput(String),
+    Other(String),
+}
+
+impl KataError {
+    pub fn json(context: impl Into<String>, source: serde_json::Error) -> Self {
+        KataError::Json {
+            context: context.into(),
+            source,
+        }
+    }
+
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        KataError::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    /// Maps a failure to start `tool` to `ToolMissing` when the binary does not exist.
+    pub fn spawn(tool: &str, source: io::Error) -> Self {
+        if source.kind() == io::ErrorKind::NotFound {
+            KataError::ToolMissing {
+                tool: tool.to_string(),
+                source,
+            }
+        } else {
+            KataError::io(format!("Failed to run {}", tool), source)
+        }
+    }
+
+    pub fn generation(step: impl Into<String>, source: KataError) -> Self {
+        KataError::GenerationFailed {
+            step: step.into(),
+            source: Box::new(source),
+        }
+    }
+
+    /// The process exit code for this error. These are stable, so scripts can rely on them.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            KataError::TestsFailed { .. } => 1,
+            KataError::InvalidInput(_) => 2,
+            KataError::ToolMissing { .. } => 3,
+            KataError::SessionMissing { .. } => 4,
+            KataError::SessionCorrupt { .. } => 5,
+            KataError::GenerationFailed { .. } => 6,
+            KataError::Io { .. } => 7,
+            KataError::Json { .. } => 8,
+            KataError::Other(_) => 9,
+            KataError::CommandFailed { .. } => 10,
+            KataError::TestsTimedOut { .. } => 11,
+            KataError::TestsOutOfMemory { .. } => 12,
+            KataError::DayMissing { .. } => 13,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            KataError::ToolMissing { .. } => "tool_missing",
+            KataError::SessionMissing { .. } => "session_missing",
+            KataError::SessionCorrupt { .. } => "session_corrupt",
+            KataError::DayMissing { .. } => "day_missing",
+            KataError::GenerationFailed { .. } => "generation_failed",
+            KataError::TestsFailed { .. } => "tests_failed",
+            KataError::TestsTimedOut { .. } => "tests_timed_out",
+            KataError::TestsOutOfMemory { .. } => "tests_out_of_memory",
+            KataError::CommandFailed { .. } => "command_failed",
+            KataError::Io { .. } => "io",
+            KataError::Json { .. } => "json",
+            KataError::InvalidInput(_) => "invalid_input",
+            KataError::Other(_) => "other",
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut causes = Vec::new();
+        let mut source = self.source();
+        while let Some(cause) = source {
+            causes.push(cause.to_string());
+            source = cause.source();
+        }
+
+        let mut error = json!({
+            "kind": self.kind(),
+            "exit_code": self.exit_code(),
+            "message": self.to_string(),
+            "causes": causes,
+        });
+        match self {
+            KataError::ToolMissing { tool, .. } => error["tool"] = json!(tool),
+            KataError::SessionMissing { path } | KataError::SessionCorrupt { path, .. } => {
+                error["path"] = json!(path)
+            }
+            KataError::DayMissing { kata_dir } => error["path"] = json!(kata_dir),
+            KataError::GenerationFailed { step, .. } => error["step"] = json!(step),
+            KataError::TestsFailed { passed, failed } => {
+                error["passed"] = json!(passed);
+                error["failed"] = json!(failed);
+            }
+            KataError::TestsTimedOut { timeout } => {
+                error["timeout_ms"] = json!(timeout.as_millis() as u64)
+            }
+            KataError::TestsOutOfMemory { limit_mb } => error["memory_limit_mb"] = json!(limit_mb),
+            KataError::CommandFailed {
+                command_line,
+                working_dir,
+                status,
+                duration,
+            } => {
+                error["command"] = json!(command_line);
+                error["working_dir"] = json!(working_dir);
+                error["status"] = json!(status);
+                error["duration_ms"] = json!(duration.as_millis() as u64);
+            }
+            _ => {}
+        }
+
+        json!({ "error": error })
+    }
+}
+
+impl fmt::Display for KataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KataError::ToolMissing { tool, .. } => {
+                write!(
+                    f,
+                    "{} is not installed. Run `kata_machine init` first.",
+                    tool
+                )
+            }
+            KataError::SessionMissing { path } => write!(
+                f,
+                "No session found at {}. Run `kata_machine generate` first.",
+                path.display()
+            ),
+            KataError::SessionCorrupt { path, .. } => {
+                write!(f, "The session file {} is corrupt.", path.display())
+            }
+            KataError::DayMissing { kata_dir } => write!(
+                f,
+                "No day folders found in {}. Run `kata_machine generate` first.",
+                kata_dir.display()
+            ),
+            KataError::GenerationFailed { step, .. } => {
+                write!(f, "Generation failed while {}.", step)
+            }
+            KataError::TestsFailed { passed, failed } => {
+                write!(f, "Tests failed: {} passed, {} failed.", passed, failed)
+            }
+            KataError::TestsTimedOut { timeout } => write!(
+                f,
+                "Tests timed out after {}s and were killed. Look for an infinite loop or runaway recursion.",
+                timeout.as_secs()
+            ),
+            KataError::TestsOutOfMemory { limit_mb } => write!(
+                f,
+                "Tests ran out of memory, they used more than {} MB and were killed.",
+                limit_mb
+            ),
+            KataError::CommandFailed {
+                command_line,
+                working_dir,
+                status,
+                duration,
+            } => {
+                write!(f, "`{}`", command_line)?;
+                if let Some(working_dir) = working_dir {
+                    write!(f, " in {}", working_dir.display())?;
+                }
+                match status {
+                    Some(code) => write!(f, " exited with status {}", code)?,
+                    None => write!(f, " was terminated by a signal")?,
+                }
+                write!(f, " after {:.1}s.", duration.as_secs_f64())
+            }
+            KataError::Io { context, .. } | KataError::Json { context, .. } => {
+                write!(f, "{}", context)
+            }
+            KataError::InvalidInput(message) | KataError::Other(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl Error for KataError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            KataError::ToolMissing { source, .. } => Some(source),
+            KataError::SessionCorrupt { source, .. } => Some(source),
+            KataError::GenerationFailed { source, .. } => Some(source.as_ref()),
+            KataError::Io { source, .. } => Some(source),
+            KataError::Json { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -1,17 +1,90 @@
+use crate::errors::KataError;
 use std::fs;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Folders created by the language toolchains rather than by the user.
+const GENERATED_FOLDERS: [&str; 4] = ["node_modules", "target", "bin", "obj"];
+const GENERATED_FILES: [&str; 2] = ["package-lock.json", "Cargo.lock"];
 
 pub fn create_file_with_content(
     file_dir: &Path,
     file_name: &str,
     content: &str,
-) -> Result<(), String> {
+) -> Result<(), KataError> {
     let file_path = file_dir.join(file_name);
-    if let Ok(mut file) = fs::File::create(&file_path) {
-        file.write_all(content.as_bytes())
-            .map_err(|e| format!("Error writing to file: {}", e))
-    } else {
-        Err(format!("Error creating file {}!", file_path.display()))
+    let mut file = fs::File::create(&file_path)
+        .map_err(|e| KataError::io(format!("Error creating file {}", file_path.display()), e))?;
+    file.write_all(content.as_bytes())
+        .map_err(|e| KataError::io(format!("Error writing to file {}", file_path.display()), e))
+}
+
+/// Lists the user-owned source files of a day folder, relative to that folder and sorted.
+pub fn collect_source_files(day_folder_path: &Path) -> Result<Vec<PathBuf>, KataError> {
+    let mut files = Vec::new();
+    collect_source_files_into(day_folder_path, Path::new(""), &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn collect_source_files_into(
+    root: &Path,
+    relative_dir: &Path,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), KataError> {
+    let entries = fs::read_dir(root.join(relative_dir))
+        .map_err(|e| KataError::io(format!("Failed to read {}", root.display()), e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| KataError::io("Failed to read directory entry", e))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let relative_path = relative_dir.join(&name);
+
+        if name.starts_with('.') {
+            continue;
+        }
+
+        if entry.path().is_dir() {
+            if !GENERATED_FOLDERS.contains(&name.as_str()) {
+                collect_source_files_into(root, &relative_path, files)?;
+            }
+        } else if !GENERATED_FILES.contains(&name.as_str()) {
+            files.push(relative_path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Remembers the content of files so they can be put back when a later step fails.
+pub struct FileBackup {
+    files: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+impl FileBackup {
+    pub fn capture(paths: &[PathBuf]) -> Self {
+        FileBackup {
+            files: paths
+                .iter()
+                .map(|path| (path.clone(), fs::read(path).ok()))
+                .collect(),
+        }
+    }
+
+    /// Restores the captured content, removing the files that did not exist before.
+    pub fn restore(&self) -> Result<(), KataError> {
+        for (path, content) in &self.files {
+            match content {
+                Some(content) => fs::write(path, content).map_err(|e| {
+                    KataError::io(format!("Failed to restore {}", path.display()), e)
+                })?,
+                None if path.exists() => fs::remove_file(path).map_err(|e| {
+                    KataError::io(format!("Failed to remove {}", path.display()), e)
+                })?,
+                None => {}
+            }
+        }
+
+        Ok(())
     }
 }
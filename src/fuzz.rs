@@ -0,0 +1,449 @@
+use crate::{
+    copy_day_source_files,
+    errors::KataError,
+    find_most_recent_day_folder,
+    languages::{
+        csharp::commands::{
+            append_csharp_calculator_test_case, write_csharp_calculator_fuzz_harness,
+        },
+        rust::commands::{
+            append_rust_calculator_test_case, use_vendored_registry,
+            write_rust_calculator_fuzz_harness,
+        },
+        typescript::commands::{
+            append_typescript_calculator_test_case, write_typescript_calculator_fuzz_harness,
+        },
+    },
+    run_day_tests, FileRole, Kata, KataInput, Language, LimitExceeded, RunLimits,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The shrinking stops after this many rounds, even if the counter-example could get smaller.
+const MAX_SHRINK_ROUNDS: usize = 100;
+
+/// How `fuzz` runs.
+#[derive(Default)]
+pub struct FuzzOptions {
+    /// The number of random inputs, 500 when 0.
+    pub runs: usize,
+    /// Seeds the input generator, so a run can be repeated. Taken from the clock when unset.
+    pub seed: Option<u64>,
+    /// Add the counter-example as a test case to the day's test file.
+    pub append: bool,
+}
+
+/// The two numbers the calculator adds.
+type Case = (String, String);
+
+/// The calculator as it should behave: the sum of two 32-bit integers, which may be surrounded
+/// by whitespace, and `None` for anything else or a sum that does not fit.
+fn calculator_reference((num1, num2): &Case) -> Option<i32> {
+    let num1 = num1.trim().parse::<i32>().ok()?;
+    let num2 = num2.trim().parse::<i32>().ok()?;
+    num1.checked_add(num2)
+}
+
+/// What the solution is expected to print for the case, as the harness does.
+fn expected_output(case: &Case) -> String {
+    calculator_reference(case).map_or_else(|| "error".to_string(), |sum| sum.to_string())
+}
+
+/// A xorshift generator, enough to pick inputs.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // xorshift never leaves 0
+        Rng(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+/// A number for the calculator, usually a plain integer of up to ten digits, and now and
+/// then an edge case: a bound of the 32-bit integers, an empty string, a sign or padding.
+fn generate_operand(rng: &mut Rng) -> String {
+    const EDGES: [&str; 10] = [
+        "0",
+        "-0",
+        "1",
+        "-1",
+        "2147483647",
+        "-2147483648",
+        "2147483648",
+        "-2147483649",
+        "-",
+        "+",
+    ];
+
+    match rng.below(20) {
+        0 | 1 => String::new(),
+        2..=4 => EDGES[rng.below(EDGES.len() as u64) as usize].to_string(),
+        roll => {
+            let digits = 1 + rng.below(10);
+            let mut number: String = (0..digits)
+                .map(|_| char::from(b'0' + rng.below(10) as u8))
+                .collect();
+            if rng.below(2) == 0 {
+                number.insert(0, '-');
+            }
+            match roll {
+                5 => format!("+{}", number.trim_start_matches('-')),
+                6 => format!("00{}", number.trim_start_matches('-')),
+                7 => format!(" {} ", number),
+                _ => number,
+            }
+        }
+    }
+}
+
+/// How far the case is from the simplest one, which shrinking only ever decreases.
+fn complexity((num1, num2): &Case) -> (usize, u128) {
+    let magnitude = |num: &str| {
+        num.chars()
+            .filter(char::is_ascii_digit)
+            .collect::<String>()
+            .parse::<u128>()
+            .unwrap_or(0)
+    };
+    (num1.len() + num2.len(), magnitude(num1) + magnitude(num2))
+}
+
+/// Simpler versions of an operand: empty, zero, one character shorter, or a number closer to zero.
+fn shrink_operand(num: &str) -> Vec<String> {
+    let mut candidates = vec![String::new(), "0".to_string()];
+    for (index, _) in num.char_indices() {
+        let mut shorter = num.to_string();
+        shorter.remove(index);
+        candidates.push(shorter);
+    }
+    // Keeping any padding, which may be what the solution trips over
+    let trimmed = num.trim();
+    if let Ok(value) = trimmed.parse::<i128>() {
+        for smaller in [value / 2, value - value.signum()] {
+            candidates.push(num.replacen(trimmed, &smaller.to_string(), 1));
+        }
+    }
+    candidates
+}
+
+/// The simpler cases to try next, simplest first.
+fn shrink_candidates(case: &Case) -> Vec<Case> {
+    let (num1, num2) = case;
+    let mut candidates: Vec<Case> = shrink_operand(num1)
+        .into_iter()
+        .map(|shrunk| (shrunk, num2.clone()))
+        .chain(
+            shrink_operand(num2)
+                .into_iter()
+                .map(|shrunk| (num1.clone(), shrunk)),
+        )
+        .filter(|candidate| complexity(candidate) < complexity(case))
+        .collect();
+    candidates.sort_by_cached_key(|candidate| (complexity(candidate), candidate.clone()));
+    candidates.dedup();
+    candidates
+}
+
+/// A copy of the day whose tests are replaced by a harness running the solution on a file of
+/// inputs. It lives in the language folder, where the shared dependencies of the days are found.
+struct Harness {
+    path: PathBuf,
+    language: Language,
+    limits: RunLimits,
+}
+
+impl Harness {
+    fn build(
+        root: &Path,
+        day_folder_path: &Path,
+        language: Language,
+        limits: &RunLimits,
+    ) -> Result<Self, KataError> {
+        let path = root.join(language.as_str()).join("fuzzing");
+        if path.exists() {
+            fs::remove_dir_all(&path)
+                .map_err(|e| KataError::io("Failed to remove a leftover fuzzing folder", e))?;
+        }
+        let harness = Harness {
+            path,
+            language,
+            limits: *limits,
+        };
+
+        fs::create_dir_all(&harness.path).map_err(|e| {
+            KataError::io(format!("Failed to create {}", harness.path.display()), e)
+        })?;
+        copy_day_source_files(day_folder_path, &harness.path, language, FileRole::Test)?;
+        let (inputs_path, outputs_path) = (harness.inputs_path(), harness.outputs_path());
+        match language {
+            Language::Rust => {
+                write_rust_calculator_fuzz_harness(&harness.path, &inputs_path, &outputs_path)?;
+                use_vendored_registry(root, &harness.path)?;
+            }
+            Language::TS => write_typescript_calculator_fuzz_harness(
+                &harness.path,
+                &inputs_path,
+                &outputs_path,
+            )?,
+            Language::CSharp => {
+                write_csharp_calculator_fuzz_harness(&harness.path, &inputs_path, &outputs_path)?
+            }
+        }
+        Ok(harness)
+    }
+
+    fn inputs_path(&self) -> PathBuf {
+        self.path.join("fuzz_inputs.txt")
+    }
+
+    fn outputs_path(&self) -> PathBuf {
+        self.path.join("fuzz_outputs.txt")
+    }
+
+    /// The outputs of the solution for the cases, in the same order.
+    fn run(&self, cases: &[Case]) -> Result<Vec<String>, KataError> {
+        let inputs: String = cases
+            .iter()
+            .map(|(num1, num2)| format!("{}\t{}\n", num1, num2))
+            .collect();
+        fs::write(self.inputs_path(), inputs)
+            .map_err(|e| KataError::io("Failed to write the fuzz inputs", e))?;
+        let _ = fs::remove_file(self.outputs_path());
+
+        let report = run_day_tests(&self.path, self.language, &self.limits)?;
+        match report.limit_exceeded {
+            Some(LimitExceeded::Timeout(timeout)) => {
+                return Err(KataError::TestsTimedOut { timeout })
+            }
+            Some(LimitExceeded::Memory(limit_mb)) => {
+                return Err(KataError::TestsOutOfMemory { limit_mb })
+            }
+            None => {}
+        }
+
+        let outputs: Vec<String> = fs::read_to_string(self.outputs_path())
+            .unwrap_or_default()
+            .lines()
+            .map(|line| line.trim().to_string())
+            .collect();
+        if outputs.len() != cases.len() {
+            return Err(KataError::Other(
+                "The fuzz harness did not run, check that the solution builds with `kata_machine test`."
+                    .to_string(),
+            ));
+        }
+        Ok(outputs)
+    }
+
+    /// The cases the solution disagrees with the reference on, with what it printed.
+    fn disagreements(&self, cases: &[Case]) -> Result<Vec<(Case, String)>, KataError> {
+        let outputs = self.run(cases)?;
+        Ok(cases
+            .iter()
+            .cloned()
+            .zip(outputs)
+            .filter(|(case, output)| *output != expected_output(case))
+            .collect())
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Runs random inputs through the solution of the most recent day and the reference, and
+/// shrinks the first disagreement to a minimal counter-example.
+pub(crate) fn fuzz(
+    root: &Path,
+    input: &KataInput,
+    limits: &RunLimits,
+    options: &FuzzOptions,
+) -> Result<(), KataError> {
+    if input.kata != Kata::Calculator {
+        return Err(KataError::InvalidInput(format!(
+            "The {} kata has no reference to fuzz against, only calculator has.",
+            input.kata.as_str()
+        )));
+    }
+
+    let kata_dir = root.join(input.language.as_str()).join(input.kata.as_str());
+    let day_folder_path =
+        find_most_recent_day_folder(&kata_dir).ok_or_else(|| KataError::DayMissing {
+            kata_dir: kata_dir.clone(),
+        })?;
+
+    let runs = if options.runs == 0 { 500 } else { options.runs };
+    let seed = options.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(1, |elapsed| elapsed.as_nanos() as u64)
+    });
+    let mut rng = Rng::new(seed);
+    let cases: Vec<Case> = (0..runs)
+        .map(|_| (generate_operand(&mut rng), generate_operand(&mut rng)))
+        .collect();
+
+    let harness = Harness::build(root, &day_folder_path, input.language, limits)?;
+    let disagreements = harness.disagreements(&cases)?;
+    let Some(first) = disagreements.first().cloned() else {
+        println!(
+            "No disagreement with the reference in {} inputs (seed {}).",
+            runs, seed
+        );
+        return Ok(());
+    };
+    println!(
+        "The solution disagrees with the reference on {} of {} inputs (seed {}), shrinking...",
+        disagreements.len(),
+        runs,
+        seed
+    );
+
+    let (mut case, mut output) = first;
+    for _ in 0..MAX_SHRINK_ROUNDS {
+        let candidates = shrink_candidates(&case);
+        if candidates.is_empty() {
+            break;
+        }
+        match harness.disagreements(&candidates)?.into_iter().next() {
+            Some(smaller) => (case, output) = smaller,
+            None => break,
+        }
+    }
+    drop(harness);
+
+    println!("\nCounter-example: {}", call(input.language, &case));
+    println!("  expected: {}", expected_output(&case));
+    println!("  actual:   {}", output);
+
+    if options.append {
+        let case_ref = (case.0.as_str(), case.1.as_str());
+        let expected = calculator_reference(&case);
+        let path = match input.language {
+            Language::Rust => {
+                append_rust_calculator_test_case(&day_folder_path, case_ref, expected)
+            }
+            Language::TS => {
+                append_typescript_calculator_test_case(&day_folder_path, case_ref, expected)
+            }
+            Language::CSharp => {
+                append_csharp_calculator_test_case(&day_folder_path, case_ref, expected)
+            }
+        }?;
+        println!(
+            "\nAdded the counter-example as a test case to {}.",
+            path.display()
+        );
+    }
+
+    Err(KataError::TestsFailed {
+        passed: (runs - disagreements.len()) as u32,
+        failed: disagreements.len() as u32,
+    })
+}
+
+/// The call of the calculator on the case, as the language writes it.
+fn call(language: Language, (num1, num2): &Case) -> String {
+    let function = match language {
+        Language::Rust => "add_numbers",
+        Language::TS => "addNumbers",
+        Language::CSharp => "Calculator.AddNumbers",
+    };
+    format!("{}({:?}, {:?})", function, num1, num2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(num1: &str, num2: &str) -> Case {
+        (num1.to_string(), num2.to_string())
+    }
+
+    #[test]
+    fn calculator_reference_adds_32_bit_integers() {
+        assert_eq!(calculator_reference(&case("2", "3")), Some(5));
+        assert_eq!(calculator_reference(&case(" -5 ", "+10")), Some(5));
+        assert_eq!(calculator_reference(&case("007", "-0")), Some(7));
+        assert_eq!(
+            calculator_reference(&case("2147483647", "-2147483648")),
+            Some(-1)
+        );
+    }
+
+    #[test]
+    fn calculator_reference_rejects_invalid_numbers_and_overflows() {
+        for (num1, num2) in [
+            ("", "1"),
+            ("-", "1"),
+            ("1.5", "1"),
+            ("1 2", "1"),
+            ("2147483648", "0"),
+            ("2147483647", "1"),
+            ("-2147483648", "-1"),
+        ] {
+            assert_eq!(
+                calculator_reference(&case(num1, num2)),
+                None,
+                "{}, {}",
+                num1,
+                num2
+            );
+        }
+    }
+
+    #[test]
+    fn shrink_candidates_are_simpler_and_sorted() {
+        let start = case(" -2147483649 ", "+0042");
+        let candidates = shrink_candidates(&start);
+        assert!(!candidates.is_empty());
+        assert!(candidates
+            .iter()
+            .all(|candidate| complexity(candidate) < complexity(&start)));
+        assert!(candidates
+            .windows(2)
+            .all(|pair| (complexity(&pair[0]), &pair[0]) < (complexity(&pair[1]), &pair[1])));
+    }
+
+    #[test]
+    fn shrinking_terminates() {
+        let mut rng = Rng::new(7);
+        for _ in 0..200 {
+            // The path fuzz takes when even the simplest candidate still disagrees
+            let mut current = (generate_operand(&mut rng), generate_operand(&mut rng));
+            let mut rounds = 0;
+            while let Some(next) = shrink_candidates(&current).into_iter().next() {
+                assert!(complexity(&next) < complexity(&current));
+                current = next;
+                rounds += 1;
+                assert!(
+                    rounds <= MAX_SHRINK_ROUNDS,
+                    "no end to shrinking {:?}",
+                    current
+                );
+            }
+            assert_eq!(current, case("", ""));
+        }
+    }
+
+    #[test]
+    fn shrink_operand_keeps_padding() {
+        assert!(shrink_operand(" 42 ").contains(&" 21 ".to_string()));
+    }
+}
@@ -0,0 +1,365 @@
+use crate::{
+    console_utils::prompt,
+    diff::unified_diff,
+    errors::KataError,
+    file_utils::{collect_source_files, create_file_with_content},
+    languages::test_report::TestReport,
+    process_utils::LimitExceeded,
+    time_utils::{format_duration, unix_timestamp},
+    Kata, Language,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct TestRun {
+    pub timestamp: u64,
+    pub passed: bool,
+    #[serde(default)]
+    pub passed_tests: u32,
+    #[serde(default)]
+    pub failed_tests: u32,
+    /// `timed_out` or `out_of_memory` when the supervisor stopped the run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_exceeded: Option<String>,
+    /// Files added, modified or removed since the previous run.
+    #[serde(default)]
+    pub changed_files: Vec<String>,
+    /// Maps every source file, relative to the day folder, to the object holding its content.
+    pub files: BTreeMap<String, String>,
+}
+
+pub fn record_test_run(
+    root: &Path,
+    language: Language,
+    kata: Kata,
+    day_folder_path: &Path,
+    report: &TestReport,
+) -> Result<(), KataError> {
+    let files = snapshot_files(root, day_folder_path)?;
+
+    let day = day_name(day_folder_path);
+    let mut runs = load_test_runs(root, language, kata, &day)?;
+    // The first run is compared against the day as it was generated
+    let template;
+    let previous_files = match runs.last() {
+        Some(run) => &run.files,
+        None => {
+            template = load_template(root, language, kata, &day)?;
+            &template
+        }
+    };
+    let changed_files = previous_files
+        .keys()
+        .chain(files.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|path| previous_files.get(*path) != files.get(*path))
+        .cloned()
+        .collect();
+
+    runs.push(TestRun {
+        timestamp: unix_timestamp(),
+        passed: report.success,
+        passed_tests: report.passed_tests,
+        failed_tests: report.failed_tests,
+        limit_exceeded: report.limit_exceeded.map(|limit| {
+            match limit {
+                LimitExceeded::Timeout(_) => "timed_out",
+                LimitExceeded::Memory(_) => "out_of_memory",
+            }
+            .to_string()
+        }),
+        changed_files,
+        files,
+    });
+
+    let history_path = history_path(root, language, kata, &day);
+    let history_dir = history_path.parent().unwrap();
+    fs::create_dir_all(history_dir)
+        .map_err(|e| KataError::io("Failed to create history directory", e))?;
+
+    let history_json = serde_json::to_string(&runs)
+        .map_err(|e| KataError::json("Error serializing test history to JSON", e))?;
+    create_file_with_content(history_dir, &format!("{}.json", day), &history_json)
+}
+
+/// Snapshots the files of a freshly generated day, so its first test run only lists the files
+/// changed since.
+pub fn record_template(
+    root: &Path,
+    language: Language,
+    kata: Kata,
+    day_folder_path: &Path,
+) -> Result<(), KataError> {
+    let files = snapshot_files(root, day_folder_path)?;
+
+    let day = day_name(day_folder_path);
+    let template_path = template_path(root, language, kata, &day);
+    let template_dir = template_path.parent().unwrap();
+    fs::create_dir_all(template_dir)
+        .map_err(|e| KataError::io("Failed to create history directory", e))?;
+
+    let template_json = serde_json::to_string(&files)
+        .map_err(|e| KataError::json("Error serializing template snapshot to JSON", e))?;
+    create_file_with_content(
+        template_dir,
+        &format!("{}.template.json", day),
+        &template_json,
+    )
+}
+
+/// Days generated before templates were recorded have none, and count every file as changed.
+fn load_template(
+    root: &Path,
+    language: Language,
+    kata: Kata,
+    day: &str,
+) -> Result<BTreeMap<String, String>, KataError> {
+    let template_path = template_path(root, language, kata, day);
+    if !template_path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let template_json = fs::read_to_string(&template_path)
+        .map_err(|e| KataError::io("Error reading template snapshot", e))?;
+    serde_json::from_str(&template_json)
+        .map_err(|e| KataError::json("Error parsing template snapshot", e))
+}
+
+/// Stores the source files of the day and maps each of them to its object.
+fn snapshot_files(
+    root: &Path,
+    day_folder_path: &Path,
+) -> Result<BTreeMap<String, String>, KataError> {
+    let objects_dir = objects_dir(root);
+    fs::create_dir_all(&objects_dir)
+        .map_err(|e| KataError::io("Failed to create snapshot directory", e))?;
+
+    let mut files = BTreeMap::new();
+    for relative_path in collect_source_files(day_folder_path)? {
+        let content = fs::read(day_folder_path.join(&relative_path))
+            .map_err(|e| KataError::io(format!("Failed to read {}", relative_path.display()), e))?;
+        let hash = store_object(&objects_dir, &content)?;
+        files.insert(relative_path.to_string_lossy().replace('\\', "/"), hash);
+    }
+    Ok(files)
+}
+
+pub fn load_test_runs(
+    root: &Path,
+    language: Language,
+    kata: Kata,
+    day: &str,
+) -> Result<Vec<TestRun>, KataError> {
+    let history_path = history_path(root, language, kata, day);
+    if !history_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let history_json = fs::read_to_string(&history_path)
+        .map_err(|e| KataError::io("Error reading test history", e))?;
+    serde_json::from_str(&history_json)
+        .map_err(|e| KataError::json("Error parsing test history", e))
+}
+
+pub fn read_snapshot_file(root: &Path, hash: &str) -> Result<String, KataError> {
+    let content = fs::read(objects_dir(root).join(hash))
+        .map_err(|e| KataError::io(format!("Failed to read snapshot {}", hash), e))?;
+    Ok(String::from_utf8_lossy(&content).to_string())
+}
+
+pub fn replay_day(root: &Path, language: Language, kata: Kata, day: &str) -> Result<(), KataError> {
+    let runs = load_test_runs(root, language, kata, day)?;
+    if runs.is_empty() {
+        return Err(KataError::InvalidInput(format!(
+            "No test runs recorded for {}/{}/{}.",
+            language.as_str(),
+            kata.as_str(),
+            day
+        )));
+    }
+
+    let first_timestamp = runs[0].timestamp;
+    let empty = BTreeMap::new();
+
+    for (index, run) in runs.iter().enumerate() {
+        println!(
+            "=== Run {}/{} (+{}) - {} ({} passed, {} failed) ===",
+            index + 1,
+            runs.len(),
+            format_duration(run.timestamp.saturating_sub(first_timestamp)),
+            match (run.passed, run.limit_exceeded.as_deref()) {
+                (_, Some("timed_out")) => "TIMED OUT",
+                (_, Some(_)) => "OUT OF MEMORY",
+                (true, None) => "GREEN",
+                (false, None) => "RED",
+            },
+            run.passed_tests,
+            run.failed_tests
+        );
+
+        let previous = if index == 0 {
+            &empty
+        } else {
+            &runs[index - 1].files
+        };
+        print_snapshot_diff(root, previous, &run.files)?;
+
+        if index + 1 < runs.len() && !prompt("Press Enter for the next run (q to quit)")? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn print_snapshot_diff(
+    root: &Path,
+    previous: &BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+) -> Result<(), KataError> {
+    let paths: BTreeSet<&String> = previous.keys().chain(current.keys()).collect();
+    let mut changed = false;
+
+    for path in paths {
+        let (old_hash, new_hash) = (previous.get(path), current.get(path));
+        if old_hash == new_hash {
+            continue;
+        }
+
+        let old_content = match old_hash {
+            Some(hash) => read_snapshot_file(root, hash)?,
+            None => String::new(),
+        };
+        let new_content = match new_hash {
+            Some(hash) => read_snapshot_file(root, hash)?,
+            None => String::new(),
+        };
+        let old_name = old_hash.map_or("/dev/null".to_string(), |_| format!("a/{}", path));
+        let new_name = new_hash.map_or("/dev/null".to_string(), |_| format!("b/{}", path));
+
+        print!(
+            "{}",
+            unified_diff(&old_content, &new_content, &old_name, &new_name)
+        );
+        changed = true;
+    }
+
+    if !changed {
+        println!("No changes since the previous run.");
+    }
+
+    Ok(())
+}
+
+pub fn objects_dir(root: &Path) -> PathBuf {
+    root.join(".kata").join("objects")
+}
+
+pub fn history_path(root: &Path, language: Language, kata: Kata, day: &str) -> PathBuf {
+    root.join(".kata")
+        .join("history")
+        .join(language.as_str())
+        .join(kata.as_str())
+        .join(format!("{}.json", day))
+}
+
+/// The pair and mob turns of a day, next to its test runs.
+pub fn pairing_path(root: &Path, language: Language, kata: Kata, day: &str) -> PathBuf {
+    history_path(root, language, kata, day).with_file_name(format!("{}.pairing.json", day))
+}
+
+/// The files of the day as it was generated, next to its test runs.
+pub fn template_path(root: &Path, language: Language, kata: Kata, day: &str) -> PathBuf {
+    history_path(root, language, kata, day).with_file_name(format!("{}.template.json", day))
+}
+
+fn day_name(day_folder_path: &Path) -> String {
+    day_folder_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Stores the content and returns the name of its object. Objects are content-addressed, so
+/// unchanged files are only stored once. Two contents sharing a hash are told apart by
+/// comparing the bytes, the later one going under the first free `<hash>-<n>`.
+fn store_object(objects_dir: &Path, content: &[u8]) -> Result<String, KataError> {
+    let hash = content_hash(content);
+    let mut name = hash.clone();
+    for collisions in 1.. {
+        let object_path = objects_dir.join(&name);
+        match fs::read(&object_path) {
+            Ok(existing) if existing == content => break,
+            Ok(_) => name = format!("{}-{}", hash, collisions),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                fs::write(&object_path, content)
+                    .map_err(|e| KataError::io("Failed to write snapshot", e))?;
+                break;
+            }
+            Err(e) => return Err(KataError::io("Failed to read snapshot", e)),
+        }
+    }
+    Ok(name)
+}
+
+/// 64-bit FNV-1a, which is stable across platforms and Rust versions.
+pub(crate) fn content_hash(content: &[u8]) -> String {
+    let hash = content.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(success: bool) -> TestReport {
+        TestReport {
+            success,
+            passed_tests: if success { 1 } else { 0 },
+            failed_tests: if success { 0 } else { 1 },
+            limit_exceeded: None,
+        }
+    }
+
+    #[test]
+    fn the_first_run_lists_the_files_changed_since_generation() {
+        let root =
+            std::env::temp_dir().join(format!("kata_machine_history_{}", std::process::id()));
+        let day_folder_path = root.join("rust").join("calculator").join("day1");
+        fs::create_dir_all(day_folder_path.join("src")).unwrap();
+        fs::write(
+            day_folder_path.join("src").join("lib.rs"),
+            "pub fn add() {}\n",
+        )
+        .unwrap();
+        fs::write(day_folder_path.join("src").join("test.rs"), "").unwrap();
+
+        let (language, kata) = (Language::Rust, Kata::Calculator);
+        record_template(&root, language, kata, &day_folder_path).unwrap();
+        fs::write(day_folder_path.join("src").join("test.rs"), "#[test]\n").unwrap();
+        record_test_run(&root, language, kata, &day_folder_path, &report(false)).unwrap();
+        fs::write(
+            day_folder_path.join("src").join("lib.rs"),
+            "pub fn add() -> i32 { 0 }\n",
+        )
+        .unwrap();
+        record_test_run(&root, language, kata, &day_folder_path, &report(true)).unwrap();
+
+        let runs = load_test_runs(&root, language, kata, "day1");
+        fs::remove_dir_all(&root).unwrap();
+        let changed_files = runs
+            .unwrap()
+            .into_iter()
+            .map(|run| run.changed_files)
+            .collect::<Vec<_>>();
+        assert_eq!(changed_files, [["src/test.rs"], ["src/lib.rs"]]);
+    }
+}
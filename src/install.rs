@@ -0,0 +1,418 @@
+use crate::{errors::KataError, process_utils, Language};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The Node release `init --user` unpacks into `~/.local`.
+const NODE_VERSION: &str = "20.18.0";
+/// The .NET channel installed by either mode.
+const DOTNET_CHANNEL: &str = "8.0";
+
+/// How `init` installs the missing tools.
+#[derive(Default)]
+pub struct InitOptions {
+    /// The languages to set up, all of them when empty.
+    pub languages: Vec<Language>,
+    /// Install into the home folder without sudo: rustup, dotnet-install.sh and a Node tarball.
+    pub user: bool,
+    /// Print the plan without installing anything.
+    pub dry_run: bool,
+    /// Install without asking first, and let rustup install without asking too.
+    pub skip_confirmation: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum PackageManager {
+    Apt,
+    Dnf,
+    Pacman,
+    Zypper,
+    Apk,
+}
+
+impl PackageManager {
+    const ALL: [PackageManager; 5] = [
+        PackageManager::Apt,
+        PackageManager::Dnf,
+        PackageManager::Pacman,
+        PackageManager::Zypper,
+        PackageManager::Apk,
+    ];
+
+    fn program(&self) -> &'static str {
+        match self {
+            PackageManager::Apt => "apt-get",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Pacman => "pacman",
+            PackageManager::Zypper => "zypper",
+            PackageManager::Apk => "apk",
+        }
+    }
+
+    /// The first package manager found on the PATH.
+    pub(crate) fn detect() -> Option<Self> {
+        PackageManager::ALL
+            .into_iter()
+            .find(|manager| is_on_path(manager.program()))
+    }
+
+    /// The package providing the tool, as each distribution names it.
+    fn package(&self, tool: Tool) -> &'static str {
+        match (self, tool) {
+            (PackageManager::Pacman, Tool::DotNet) => "dotnet-sdk",
+            (PackageManager::Apk, Tool::DotNet) => "dotnet8-sdk",
+            (_, Tool::DotNet) => "dotnet-sdk-8.0",
+            (PackageManager::Zypper, Tool::Node) => "npm-default",
+            (_, Tool::Node) => "npm",
+            (PackageManager::Apk, Tool::Linker) => "build-base",
+            (_, Tool::Linker) => "gcc",
+            (_, Tool::Rust) => unreachable!("rust is installed with rustup"),
+        }
+    }
+
+    fn install_command(&self, package: &str) -> Vec<String> {
+        let args: &[&str] = match self {
+            PackageManager::Apt => &["install", "-y"],
+            PackageManager::Dnf => &["install", "-y"],
+            PackageManager::Pacman => &["-S", "--needed", "--noconfirm"],
+            PackageManager::Zypper => &["--non-interactive", "install"],
+            PackageManager::Apk => &["add"],
+        };
+        ["sudo", self.program()]
+            .into_iter()
+            .chain(args.iter().copied())
+            .chain([package])
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Tool {
+    Rust,
+    /// The C compiler rustc links with.
+    Linker,
+    Node,
+    DotNet,
+}
+
+impl Tool {
+    pub(crate) const ALL: [Tool; 4] = [Tool::Rust, Tool::Linker, Tool::Node, Tool::DotNet];
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Tool::Rust => "rust",
+            Tool::Linker => "gcc",
+            Tool::Node => "npm",
+            Tool::DotNet => "dotnet",
+        }
+    }
+
+    /// The binary whose `--version` tells whether the tool is installed.
+    fn binary(&self) -> &'static str {
+        match self {
+            Tool::Rust => "rustc",
+            Tool::Linker => "cc",
+            Tool::Node => "npm",
+            Tool::DotNet => "dotnet",
+        }
+    }
+}
+
+/// One command of the plan.
+pub(crate) struct InstallStep {
+    pub tool: Tool,
+    /// The program and its arguments.
+    command: Vec<String>,
+}
+
+impl InstallStep {
+    fn shell(tool: Tool, script: String) -> Self {
+        InstallStep {
+            tool,
+            command: vec!["sh".to_string(), "-c".to_string(), script],
+        }
+    }
+
+    pub(crate) fn run(&self) -> Result<(), KataError> {
+        process_utils::run(Command::new(&self.command[0]).args(&self.command[1..])).map(|_| ())
+    }
+}
+
+impl fmt::Display for InstallStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.command.as_slice() {
+            [sh, c, script] if sh == "sh" && c == "-c" => {
+                write!(f, "sh -c {}", shell_quote(script))
+            }
+            command => write!(f, "{}", command.join(" ")),
+        }
+    }
+}
+
+/// Quotes the text as one word for `sh`, such as a home folder with spaces in it.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// What `init` installs, and how.
+pub(crate) struct InstallPlan {
+    pub steps: Vec<InstallStep>,
+    /// The tools already installed.
+    pub installed: Vec<Tool>,
+    /// What the plan cannot do, such as installing the linker without root.
+    pub notes: Vec<(Tool, String)>,
+    /// The folders the user mode installs into, which need to be on the PATH.
+    pub path_dirs: Vec<PathBuf>,
+    pub manager: Option<PackageManager>,
+}
+
+impl InstallPlan {
+    pub(crate) fn print(&self) {
+        println!(
+            "Package manager: {}",
+            self.manager.map_or("none", |manager| manager.program())
+        );
+        for tool in &self.installed {
+            println!("{} is already installed.", tool.as_str());
+        }
+        for step in &self.steps {
+            println!("Would run: {}", step);
+        }
+        for (_, note) in &self.notes {
+            println!("Note: {}", note);
+        }
+        if !self.path_dirs.is_empty() {
+            println!("Would add to the PATH: {}", join_paths(&self.path_dirs));
+        }
+    }
+
+    /// Runs the steps, putting the user mode folders on the PATH of the later commands.
+    pub(crate) fn apply(&self) -> Result<(), KataError> {
+        for step in &self.steps {
+            println!("Installing {}...", step.tool.as_str());
+            step.run()?;
+        }
+
+        if !self.path_dirs.is_empty() {
+            let path = std::env::var_os("PATH").unwrap_or_default();
+            let dirs = self
+                .path_dirs
+                .iter()
+                .cloned()
+                .chain(std::env::split_paths(&path));
+            if let Ok(path) = std::env::join_paths(dirs) {
+                std::env::set_var("PATH", path);
+            }
+            println!(
+                "Add {} to the PATH in your shell profile.",
+                join_paths(&self.path_dirs)
+            );
+        }
+        for (_, note) in &self.notes {
+            println!("Note: {}", note);
+        }
+        Ok(())
+    }
+
+    /// What the plan does with the tool, for the summary.
+    pub(crate) fn status(&self, tool: Tool, dry_run: bool) -> &'static str {
+        if self.installed.contains(&tool) {
+            "already installed"
+        } else if !self.steps.iter().any(|step| step.tool == tool) {
+            "not installed, see the note above"
+        } else if dry_run {
+            "would install"
+        } else {
+            "installed"
+        }
+    }
+}
+
+/// The table `init` ends with, of what it installed or skipped.
+#[derive(Default)]
+pub(crate) struct InitSummary {
+    rows: Vec<(String, String)>,
+}
+
+impl InitSummary {
+    pub(crate) fn add(&mut self, name: &str, status: &str) {
+        self.rows.push((name.to_string(), status.to_string()));
+    }
+
+    pub(crate) fn print(&self) {
+        let width = self
+            .rows
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        println!();
+        for (name, status) in &self.rows {
+            println!("{:<width$}  {}", name, status, width = width);
+        }
+    }
+}
+
+/// Works out the commands installing the missing tools. In the default mode they go through
+/// the package manager, with `user` they are installed into the home folder without sudo.
+/// With `assume_yes` the installers do not ask either.
+pub(crate) fn plan_install(
+    tools: &[Tool],
+    user: bool,
+    assume_yes: bool,
+) -> Result<InstallPlan, KataError> {
+    let manager = PackageManager::detect();
+    let mut plan = InstallPlan {
+        steps: Vec::new(),
+        installed: Vec::new(),
+        notes: Vec::new(),
+        path_dirs: Vec::new(),
+        manager,
+    };
+    let home = home_dir();
+
+    for &tool in tools {
+        if is_installed(tool.binary()) {
+            plan.installed.push(tool);
+            continue;
+        }
+
+        if user {
+            let home = home.as_deref().ok_or_else(|| {
+                KataError::InvalidInput("$HOME is not set, which --user installs into.".to_string())
+            })?;
+            match user_step(tool, home) {
+                Some((step, dir)) => {
+                    plan.steps.push(step);
+                    if !plan.path_dirs.contains(&dir) {
+                        plan.path_dirs.push(dir);
+                    }
+                }
+                None if tool == Tool::Linker => plan.notes.push((
+                    tool,
+                    format!(
+                        "{} needs root to install, install it with your package manager.",
+                        tool.as_str()
+                    ),
+                )),
+                None => plan.notes.push((
+                    tool,
+                    format!(
+                        "{} has no user install for {}-{}, install it yourself.",
+                        tool.as_str(),
+                        std::env::consts::OS,
+                        std::env::consts::ARCH
+                    ),
+                )),
+            }
+        } else if tool == Tool::Rust {
+            plan.steps.push(InstallStep::shell(
+                tool,
+                format!(
+                    "curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs | sh{}",
+                    if assume_yes { " -s -- -y" } else { "" }
+                ),
+            ));
+        } else {
+            let manager = manager.ok_or_else(|| {
+                KataError::InvalidInput(format!(
+                    "No supported package manager (apt, dnf, pacman, zypper or apk) found to install {}. Use `init --user` to install into your home folder instead.",
+                    tool.as_str()
+                ))
+            })?;
+            plan.steps.push(InstallStep {
+                tool,
+                command: manager.install_command(manager.package(tool)),
+            });
+        }
+    }
+
+    Ok(plan)
+}
+
+/// The user mode install of the tool and the folder holding its binaries.
+fn user_step(tool: Tool, home: &Path) -> Option<(InstallStep, PathBuf)> {
+    match tool {
+        Tool::Rust => Some((
+            InstallStep::shell(
+                tool,
+                "curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs | sh -s -- -y --no-modify-path"
+                    .to_string(),
+            ),
+            home.join(".cargo").join("bin"),
+        )),
+        Tool::DotNet => {
+            let dir = home.join(".dotnet");
+            Some((
+                InstallStep::shell(
+                    tool,
+                    format!(
+                        "curl -sSfL https://dot.net/v1/dotnet-install.sh | bash -s -- --channel {} --install-dir {}",
+                        DOTNET_CHANNEL,
+                        shell_quote(&dir.to_string_lossy())
+                    ),
+                ),
+                dir,
+            ))
+        }
+        Tool::Node => {
+            let dir = home.join(".local");
+            let platform = node_platform()?;
+            Some((
+                InstallStep::shell(
+                    tool,
+                    format!(
+                        "mkdir -p {dir} && curl -sSfL https://nodejs.org/dist/v{version}/node-v{version}-{platform}.tar.gz | tar -xz -C {dir} --strip-components=1",
+                        dir = shell_quote(&dir.to_string_lossy()),
+                        version = NODE_VERSION,
+                        platform = platform,
+                    ),
+                ),
+                dir.join("bin"),
+            ))
+        }
+        Tool::Linker => None,
+    }
+}
+
+/// The platform part of the Node tarball names, e.g. `linux-x64`.
+fn node_platform() -> Option<String> {
+    let os = match std::env::consts::OS {
+        "linux" => "linux",
+        "macos" => "darwin",
+        _ => return None,
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" => "arm64",
+        _ => return None,
+    };
+    Some(format!("{}-{}", os, arch))
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+fn join_paths(dirs: &[PathBuf]) -> String {
+    dirs.iter()
+        .map(|dir| dir.display().to_string())
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// A tool counts as installed when `<tool> --version` starts and exits successfully.
+pub(crate) fn is_installed(tool_name: &str) -> bool {
+    process_utils::run_captured(Command::new(tool_name).arg("--version"))
+        .map(|(record, _)| record.status.success())
+        .unwrap_or(false)
+}
+
+fn is_on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
@@ -1,42 +1,231 @@
-use super::generation::*;
-use crate::{file_utils::create_file_with_content, Kata};
+use super::{
+    generation::*,
+    solution::{local_package_source, write_packages_props},
+    CSharpTestFramework,
+};
+use crate::{
+    errors::KataError,
+    file_utils::create_file_with_content,
+    languages::test_report::*,
+    process_utils::{run, run_captured, run_supervised, RunLimits},
+    Kata,
+};
 use std::{
+    fs,
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    process::Command,
 };
 
-pub fn generate_csharp_files(day_folder_path: &PathBuf, kata: Kata) -> Result<(), String> {
+pub use super::generation::generate_csharp_calculator_reference;
+
+/// Targeted when no SDK can be detected.
+const DEFAULT_TARGET_FRAMEWORK: &str = "net8.0";
+
+/// Restores the day against the shared `csharp/Directory.Packages.props`, which MSBuild would not
+/// find on its own while the day is still in the staging folder. Offline restores only use the
+/// packages `init` put into the local package source.
+pub fn restore_csharp_project(
+    root: &Path,
+    day_folder_path: &Path,
+    offline: bool,
+) -> Result<(), KataError> {
+    let packages_props_path = write_packages_props(root)?;
+
+    let mut command = Command::new("dotnet");
+    command
+        .arg("restore")
+        .arg(format!(
+            "-p:DirectoryPackagesPropsPath={}",
+            packages_props_path.display()
+        ))
+        .current_dir(day_folder_path);
+    if offline {
+        let source = local_package_source(root);
+        if !source.is_dir() {
+            return Err(KataError::InvalidInput(format!(
+                "No local package source at {}, run `kata_machine init` while online first.",
+                source.display()
+            )));
+        }
+        command.arg("--source").arg(source);
+    }
+    run(&mut command)?;
+
+    Ok(())
+}
+
+/// Restores a throwaway project into the local package source, so C# days can be restored
+/// with `--offline` afterwards.
+pub fn seed_local_package_source(root: &Path) -> Result<(), KataError> {
+    let packages_props_path = write_packages_props(root)?;
+    let seed_path = root.join(".kata").join("staging").join("nuget-seed");
+    fs::create_dir_all(&seed_path)
+        .map_err(|e| KataError::io(format!("Failed to create {}", seed_path.display()), e))?;
+    create_file_with_content(
+        &seed_path,
+        "Seed.csproj",
+        &generate_seed_project_file(&newest_target_framework()),
+    )?;
+
+    let restored = run(Command::new("dotnet")
+        .arg("restore")
+        .arg(format!(
+            "-p:DirectoryPackagesPropsPath={}",
+            packages_props_path.display()
+        ))
+        .arg("--packages")
+        .arg(local_package_source(root))
+        .current_dir(&seed_path));
+
+    let _ = fs::remove_dir_all(&seed_path);
+    restored.map(|_| ())
+}
+
+pub fn write_csharp_template_files(
+    day_folder_path: &Path,
+    kata: Kata,
+    target_framework: Option<&str>,
+    test_framework: CSharpTestFramework,
+) -> Result<(), KataError> {
     let (program_file_content, test_file_content) = match kata {
-        Kata::Calculator => generate_csharp_calculator_files(),
+        Kata::Calculator => generate_csharp_calculator_files(test_framework),
         Kata::Dsa => generate_csharp_dsa_files(),
         Kata::Blank => (String::new(), String::new()),
     };
 
-    let project_file_content = generate_project_file();
+    let target_framework = target_framework
+        .map(str::to_string)
+        .unwrap_or_else(newest_target_framework);
+    let project_file_content = generate_project_file(&target_framework, test_framework);
 
     create_file_with_content(day_folder_path, "Program.cs", &program_file_content)?;
     create_file_with_content(day_folder_path, "Test.cs", &test_file_content)?;
     create_file_with_content(day_folder_path, "Kata.csproj", &project_file_content)?;
 
-    Command::new("dotnet")
-        .arg("restore")
-        .current_dir(day_folder_path)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .output()
-        .map_err(|e| format!("Failed to restore C# project: {}", e))?;
-
     Ok(())
 }
 
-pub fn run_csharp_tests(day_folder_path: &Path) -> Result<(), String> {
-    Command::new("dotnet")
-        .arg("test")
-        .current_dir(day_folder_path)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .output()
-        .map_err(|e| format!("Failed to run C# tests: {}", e))?;
+/// Replaces the tests of a copied calculator day with the hidden acceptance tests, written
+/// for the test framework of the day.
+pub fn write_csharp_calculator_acceptance_tests(
+    day_folder_path: &Path,
+    cases: &[(&str, &str, i32)],
+) -> Result<(), KataError> {
+    let (_, test_framework) = read_csharp_project_settings(day_folder_path);
+    create_file_with_content(
+        day_folder_path,
+        "Test.cs",
+        &generate_csharp_calculator_acceptance_tests(test_framework, cases),
+    )
+}
 
-    Ok(())
+/// Replaces the tests of a copied calculator day with the harness `fuzz` runs the inputs through.
+pub fn write_csharp_calculator_fuzz_harness(
+    day_folder_path: &Path,
+    inputs_path: &Path,
+    outputs_path: &Path,
+) -> Result<(), KataError> {
+    let (_, test_framework) = read_csharp_project_settings(day_folder_path);
+    create_file_with_content(
+        day_folder_path,
+        "Test.cs",
+        &generate_csharp_calculator_fuzz_harness(test_framework, inputs_path, outputs_path),
+    )
+}
+
+/// Adds a test method of the case to the end of the test class of the day, returning the file changed.
+pub fn append_csharp_calculator_test_case(
+    day_folder_path: &Path,
+    case: (&str, &str),
+    expected: Option<i32>,
+) -> Result<PathBuf, KataError> {
+    let (_, test_framework) = read_csharp_project_settings(day_folder_path);
+    let path = day_folder_path.join("Test.cs");
+    let content = fs::read_to_string(&path)
+        .map_err(|e| KataError::io(format!("Failed to read {}", path.display()), e))?;
+    let class = content.trim_end().strip_suffix('}').ok_or_else(|| {
+        KataError::InvalidInput(format!(
+            "{} does not end with the test class.",
+            path.display()
+        ))
+    })?;
+
+    let name = format!(
+        "FuzzCounterExample{}",
+        content.matches("void FuzzCounterExample").count() + 1
+    );
+    let test = generate_csharp_calculator_test_case(test_framework, &name, case, expected);
+    fs::write(
+        &path,
+        format!("{}{}}}\n", class.trim_end_matches(' '), test),
+    )
+    .map_err(|e| KataError::io(format!("Failed to write {}", path.display()), e))?;
+    Ok(path)
+}
+
+/// The target framework of the newest stable SDK `dotnet --list-sdks` reports, e.g. `net8.0` for `8.0.204`.
+fn newest_target_framework() -> String {
+    let Ok((_, output)) = run_captured(Command::new("dotnet").arg("--list-sdks")) else {
+        return DEFAULT_TARGET_FRAMEWORK.to_string();
+    };
+
+    output
+        .lines()
+        .filter_map(|line| {
+            // Previews such as `10.0.100-preview.7.25380.108` are skipped
+            let version = line.split_whitespace().next()?;
+            if version.contains('-') {
+                return None;
+            }
+            let mut numbers = version.split('.');
+            let major = numbers.next()?.parse::<u32>().ok()?;
+            let minor = numbers.next()?.parse::<u32>().ok()?;
+            Some((major, minor))
+        })
+        .max()
+        .map(|(major, minor)| format!("net{}.{}", major, minor))
+        .unwrap_or_else(|| DEFAULT_TARGET_FRAMEWORK.to_string())
+}
+
+/// The target and test framework of an existing day, read from its `Kata.csproj`.
+pub fn read_csharp_project_settings(
+    day_folder_path: &Path,
+) -> (Option<String>, CSharpTestFramework) {
+    let project = fs::read_to_string(day_folder_path.join("Kata.csproj")).unwrap_or_default();
+
+    let target_framework = project
+        .split_once("<TargetFramework>")
+        .and_then(|(_, rest)| rest.split_once("</TargetFramework>"))
+        .map(|(target_framework, _)| target_framework.trim().to_string());
+    let test_framework = CSharpTestFramework::ALL
+        .into_iter()
+        .find(|framework| project.contains(&format!("Include=\"{}\"", framework.packages()[0])))
+        .unwrap_or_default();
+
+    (target_framework, test_framework)
+}
+
+pub fn run_csharp_tests(
+    day_folder_path: &Path,
+    limits: &RunLimits,
+) -> Result<TestReport, KataError> {
+    let (record, output, limit_exceeded) = run_supervised(
+        Command::new("dotnet")
+            .arg("test")
+            .current_dir(day_folder_path),
+        limits,
+    )?;
+
+    // dotnet summarizes with "Failed!  - Failed: 1, Passed: 2, Skipped: 0, Total: 3"
+    let summary = output
+        .lines()
+        .filter(|line| line.contains("Total:"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(TestReport {
+        success: record.status.success() && limit_exceeded.is_none(),
+        passed_tests: count_after(&summary, "Passed:"),
+        failed_tests: count_after(&summary, "Failed:"),
+        limit_exceeded,
+    })
 }
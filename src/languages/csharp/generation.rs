@@ -1,4 +1,29 @@
-pub fn generate_csharp_calculator_files() -> (String, String) {
+use super::CSharpTestFramework;
+use std::path::Path;
+
+/// The namespace, class attribute, test attribute and equality assert of the test framework.
+fn test_syntax(
+    test_framework: CSharpTestFramework,
+) -> (&'static str, &'static str, &'static str, &'static str) {
+    let test_attribute = test_framework.test_attributes()[0];
+    match test_framework {
+        CSharpTestFramework::NUnit => (
+            "NUnit.Framework",
+            "[TestFixture]\n",
+            test_attribute,
+            "AreEqual",
+        ),
+        CSharpTestFramework::XUnit => ("Xunit", "", test_attribute, "Equal"),
+        CSharpTestFramework::MSTest => (
+            "Microsoft.VisualStudio.TestTools.UnitTesting",
+            "[TestClass]\n",
+            test_attribute,
+            "AreEqual",
+        ),
+    }
+}
+
+pub fn generate_csharp_calculator_files(test_framework: CSharpTestFramework) -> (String, String) {
     let program_file_content = r#"public static class Calculator {
     public static int AddNumbers(string num1, string num2) {
         // Implement the addition logic here
@@ -8,22 +33,122 @@ pub fn generate_csharp_calculator_files() -> (String, String) {
     .trim()
     .to_string();
 
-    let test_file_content = r#"using NUnit.Framework;
+    let (using, class_attribute, test_attribute, assert_equal) = test_syntax(test_framework);
+    let test_file_content = format!(
+        r#"using {using};
 
-[TestFixture]
-public class CalculatorTests {
-    [Test]
-    public void TestAddNumbers() {
-        Assert.AreEqual(5, Calculator.AddNumbers("2", "3"));
-        Assert.AreEqual(5, Calculator.AddNumbers("-5", "10"));
-        Assert.AreEqual(0, Calculator.AddNumbers("0", "0"));
+{class_attribute}public class CalculatorTests {{
+    {test_attribute}
+    public void TestAddNumbers() {{
+        Assert.{assert_equal}(5, Calculator.AddNumbers("2", "3"));
+        Assert.{assert_equal}(5, Calculator.AddNumbers("-5", "10"));
+        Assert.{assert_equal}(0, Calculator.AddNumbers("0", "0"));
         // Add more test cases here
+    }}
+}}"#
+    );
+
+    (program_file_content, test_file_content)
+}
+
+/// The reference solution of the calculator, shown by `submit --reveal`.
+pub fn generate_csharp_calculator_reference() -> String {
+    r#"public static class Calculator {
+    public static int AddNumbers(string num1, string num2) {
+        return checked(int.Parse(num1) + int.Parse(num2));
     }
 }"#
-    .trim()
-    .to_string();
+    .to_string()
+}
 
-    (program_file_content, test_file_content)
+/// The hidden calculator tests `submit` runs, one test per case so each one counts towards the score.
+pub fn generate_csharp_calculator_acceptance_tests(
+    test_framework: CSharpTestFramework,
+    cases: &[(&str, &str, i32)],
+) -> String {
+    let (using, class_attribute, test_attribute, assert_equal) = test_syntax(test_framework);
+    let tests: Vec<String> = cases
+        .iter()
+        .enumerate()
+        .map(|(index, (num1, num2, sum))| {
+            format!(
+                r#"    {test_attribute}
+    public void Acceptance{number}() {{
+        Assert.{assert_equal}({sum}, Calculator.AddNumbers("{num1}", "{num2}"));
+    }}
+"#,
+                number = index + 1,
+            )
+        })
+        .collect();
+
+    format!(
+        "using {using};\n\n{class_attribute}public class CalculatorAcceptanceTests {{\n{}}}\n",
+        tests.join("\n")
+    )
+}
+
+/// A test running the calculator on every `num1<TAB>num2` line of the inputs file, writing the
+/// sum or `error` for an exception as a line of the outputs file.
+pub fn generate_csharp_calculator_fuzz_harness(
+    test_framework: CSharpTestFramework,
+    inputs_path: &Path,
+    outputs_path: &Path,
+) -> String {
+    let (using, class_attribute, test_attribute, _) = test_syntax(test_framework);
+    format!(
+        r#"using {using};
+
+{class_attribute}public class CalculatorFuzzHarness {{
+    {test_attribute}
+    public void RunFuzzInputs() {{
+        var outputs = File.ReadAllLines({inputs}).Select(line => {{
+            var numbers = line.Split('\t');
+            try {{
+                return Calculator.AddNumbers(numbers[0], numbers[1])
+                    .ToString(System.Globalization.CultureInfo.InvariantCulture);
+            }} catch (Exception) {{
+                return "error";
+            }}
+        }});
+        File.WriteAllLines({outputs}, outputs);
+    }}
+}}
+"#,
+        inputs = string_literal(&inputs_path.display().to_string()),
+        outputs = string_literal(&outputs_path.display().to_string()),
+    )
+}
+
+/// A test method of the calculator for one case, expecting an exception when there is no sum.
+pub fn generate_csharp_calculator_test_case(
+    test_framework: CSharpTestFramework,
+    name: &str,
+    (num1, num2): (&str, &str),
+    expected: Option<i32>,
+) -> String {
+    let (_, _, test_attribute, assert_equal) = test_syntax(test_framework);
+    let call = format!(
+        "Calculator.AddNumbers({}, {})",
+        string_literal(num1),
+        string_literal(num2)
+    );
+    let body = match expected {
+        Some(sum) => format!("Assert.{}({}, {});", assert_equal, sum, call),
+        None => format!(
+            "try {{\n            {};\n        }} catch (Exception) {{\n            return;\n        }}\n        Assert.Fail(\"Expected an exception\");",
+            call
+        ),
+    };
+    format!(
+        "\n    {}\n    public void {}() {{\n        {}\n    }}\n",
+        test_attribute, name, body
+    )
+}
+
+/// The text as a double-quoted string literal, whose escapes JSON and C# share.
+fn string_literal(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
 }
 
 pub fn generate_csharp_dsa_files() -> (String, String) {
@@ -40,24 +165,128 @@ pub fn generate_csharp_dsa_files() -> (String, String) {
     (program_file_content, test_file_content)
 }
 
-pub fn generate_project_file() -> String {
-    r#"<?xml version="1.0" encoding="utf-8"?>
+/// The test packages of every test framework, with the versions `Directory.Packages.props` pins for all days.
+/// NUnit stays on 3.x, whose classic asserts the earlier days use.
+pub const CSHARP_TEST_PACKAGES: [(&str, &str); 7] = [
+    ("Microsoft.NET.Test.Sdk", "17.11.1"),
+    ("NUnit", "3.14.0"),
+    ("NUnit3TestAdapter", "4.6.0"),
+    ("xunit", "2.9.2"),
+    ("xunit.runner.visualstudio", "2.8.2"),
+    ("MSTest.TestFramework", "3.6.1"),
+    ("MSTest.TestAdapter", "3.6.1"),
+];
+
+pub fn generate_project_file(
+    target_framework: &str,
+    test_framework: CSharpTestFramework,
+) -> String {
+    let package_references: String = ["Microsoft.NET.Test.Sdk"]
+        .into_iter()
+        .chain(test_framework.packages())
+        .map(|name| format!("        <PackageReference Include=\"{}\" />\n", name))
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
 <Project Sdk="Microsoft.NET.Sdk">
 
     <PropertyGroup>
         <OutputType>Exe</OutputType>
-        <TargetFramework>net6.0</TargetFramework>
+        <TargetFramework>{}</TargetFramework>
         <ImplicitUsings>enable</ImplicitUsings>
         <Nullable>enable</Nullable>
   </PropertyGroup>
 
   <ItemGroup>
-        <PackageReference Include="Microsoft.NET.Test.Sdk" Version="16.11.0" />
-        <PackageReference Include="NUnit" Version="3.13.0" />
-        <PackageReference Include="NUnit3TestAdapter" Version="4.0.0" />
-  </ItemGroup>
-
-</Project>"#
-        .trim()
-        .to_string()
+{}  </ItemGroup>
+
+</Project>"#,
+        target_framework, package_references
+    )
+}
+
+/// A project referencing the packages of every test framework, restored to seed the local package source.
+pub fn generate_seed_project_file(target_framework: &str) -> String {
+    let package_references: String = CSHARP_TEST_PACKAGES
+        .iter()
+        .map(|(name, _)| format!("    <PackageReference Include=\"{}\" />\n", name))
+        .collect();
+
+    format!(
+        r#"<Project Sdk="Microsoft.NET.Sdk">
+  <PropertyGroup>
+    <TargetFramework>{}</TargetFramework>
+  </PropertyGroup>
+
+  <ItemGroup>
+{}  </ItemGroup>
+</Project>
+"#,
+        target_framework, package_references
+    )
+}
+
+pub fn generate_packages_props_file(marker: &str) -> String {
+    let package_versions: String = CSHARP_TEST_PACKAGES
+        .iter()
+        .map(|(name, version)| {
+            format!(
+                "    <PackageVersion Include=\"{}\" Version=\"{}\" />\n",
+                name, version
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<Project>
+  <!-- {} -->
+  <PropertyGroup>
+    <ManagePackageVersionsCentrally>true</ManagePackageVersionsCentrally>
+  </PropertyGroup>
+
+  <ItemGroup>
+{}  </ItemGroup>
+</Project>
+"#,
+        marker, package_versions
+    )
+}
+
+/// A solution with the given `(name, relative path, guid)` projects, in the format `dotnet new sln` writes.
+pub fn generate_solution_file(marker: &str, projects: &[(String, String, String)]) -> String {
+    // The project type of SDK-style C# projects
+    const CSHARP_PROJECT_TYPE: &str = "FAE04EC0-301F-11D3-BF4B-00C04F79EFBC";
+
+    let mut content = String::from(
+        "\nMicrosoft Visual Studio Solution File, Format Version 12.00\n# Visual Studio Version 17\n",
+    );
+    content.push_str(&format!("# {}\n", marker));
+    content.push_str(
+        "VisualStudioVersion = 17.0.31903.59\nMinimumVisualStudioVersion = 10.0.40219.1\n",
+    );
+    for (name, path, guid) in projects {
+        content.push_str(&format!(
+            "Project(\"{{{}}}\") = \"{}\", \"{}\", \"{{{}}}\"\nEndProject\n",
+            CSHARP_PROJECT_TYPE, name, path, guid
+        ));
+    }
+
+    content.push_str("Global\n");
+    content.push_str("\tGlobalSection(SolutionConfigurationPlatforms) = preSolution\n");
+    content.push_str("\t\tDebug|Any CPU = Debug|Any CPU\n\t\tRelease|Any CPU = Release|Any CPU\n");
+    content.push_str("\tEndGlobalSection\n");
+    content.push_str("\tGlobalSection(ProjectConfigurationPlatforms) = postSolution\n");
+    for (_, _, guid) in projects {
+        for configuration in ["Debug", "Release"] {
+            content.push_str(&format!(
+                "\t\t{{{0}}}.{1}|Any CPU.ActiveCfg = {1}|Any CPU\n\t\t{{{0}}}.{1}|Any CPU.Build.0 = {1}|Any CPU\n",
+                guid, configuration
+            ));
+        }
+    }
+    content.push_str("\tEndGlobalSection\n");
+    content.push_str("\tGlobalSection(SolutionProperties) = preSolution\n\t\tHideSolutionNode = FALSE\n\tEndGlobalSection\n");
+    content.push_str("EndGlobal\n");
+    content
 }
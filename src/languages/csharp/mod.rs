@@ -1,2 +1,69 @@
 pub mod commands;
 mod generation;
+pub mod solution;
+
+use crate::install::Tool;
+
+/// The tools `init` installs for C# days.
+pub(crate) const REQUIRED_TOOLS: [Tool; 1] = [Tool::DotNet];
+
+/// A target framework moniker of .NET 5 or later, like `net8.0`.
+pub fn is_valid_target_framework(target_framework: &str) -> bool {
+    target_framework
+        .strip_prefix("net")
+        .and_then(|version| version.split_once('.'))
+        .is_some_and(|(major, minor)| major.parse::<u32>().is_ok() && minor.parse::<u32>().is_ok())
+}
+
+/// The framework the tests of a C# day are written with.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum CSharpTestFramework {
+    #[default]
+    NUnit,
+    XUnit,
+    MSTest,
+}
+
+impl CSharpTestFramework {
+    pub const ALL: [CSharpTestFramework; 3] = [
+        CSharpTestFramework::NUnit,
+        CSharpTestFramework::XUnit,
+        CSharpTestFramework::MSTest,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CSharpTestFramework::NUnit => "nunit",
+            CSharpTestFramework::XUnit => "xunit",
+            CSharpTestFramework::MSTest => "mstest",
+        }
+    }
+
+    pub fn get(s: &str) -> Option<Self> {
+        match s {
+            "nunit" => Some(CSharpTestFramework::NUnit),
+            "xunit" => Some(CSharpTestFramework::XUnit),
+            "mstest" => Some(CSharpTestFramework::MSTest),
+            _ => None,
+        }
+    }
+
+    /// The attributes marking a test, the one the templates use first. The data driven
+    /// `[TestCase(` and `[DataTestMethod]` count too, `[Theory]` once for all its data rows.
+    pub(crate) fn test_attributes(&self) -> &'static [&'static str] {
+        match self {
+            CSharpTestFramework::NUnit => &["[Test]", "[TestCase("],
+            CSharpTestFramework::XUnit => &["[Fact]", "[Theory]"],
+            CSharpTestFramework::MSTest => &["[TestMethod]", "[DataTestMethod]"],
+        }
+    }
+
+    /// The packages a day references, besides the test SDK every framework needs.
+    fn packages(&self) -> [&'static str; 2] {
+        match self {
+            CSharpTestFramework::NUnit => ["NUnit", "NUnit3TestAdapter"],
+            CSharpTestFramework::XUnit => ["xunit", "xunit.runner.visualstudio"],
+            CSharpTestFramework::MSTest => ["MSTest.TestFramework", "MSTest.TestAdapter"],
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{errors::KataError, history::content_hash, list_day_folders, Kata, Language};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::generation::{
+    generate_packages_props_file, generate_solution_file, CSHARP_TEST_PACKAGES,
+};
+
+/// Marks the `csharp/Katas.sln` and `csharp/Directory.Packages.props` that kata_machine generated.
+const SOLUTION_MARKER: &str =
+    "Managed by kata_machine, `generate` and `clean` keep the projects in sync with the day folders.";
+
+pub fn solution_path(root: &Path) -> PathBuf {
+    root.join(Language::CSharp.as_str()).join("Katas.sln")
+}
+
+pub fn packages_props_path(root: &Path) -> PathBuf {
+    root.join(Language::CSharp.as_str())
+        .join("Directory.Packages.props")
+}
+
+/// The folder `init` fills with the test packages, which `--offline` restores from.
+pub fn local_package_source(root: &Path) -> PathBuf {
+    root.join(".kata").join("nuget")
+}
+
+/// Writes the `Directory.Packages.props` pinning the test package versions of every day.
+pub fn write_packages_props(root: &Path) -> Result<PathBuf, KataError> {
+    let path = packages_props_path(root);
+    let content = generate_packages_props_file(SOLUTION_MARKER);
+    match fs::read_to_string(&path) {
+        Ok(existing) if existing == content => return Ok(path),
+        Ok(existing) if !existing.contains(SOLUTION_MARKER) => {
+            return Err(KataError::InvalidInput(format!(
+                "{} already exists and was not generated by kata_machine.",
+                path.display()
+            )))
+        }
+        _ => {}
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| KataError::io(format!("Failed to create {}", parent.display()), e))?;
+    }
+    fs::write(&path, content)
+        .map_err(|e| KataError::io(format!("Failed to write {}", path.display()), e))?;
+    Ok(path)
+}
+
+/// The changes needed to make the solution projects match the C# day folders.
+pub struct CSharpSolutionSync {
+    pub path: PathBuf,
+    /// A line per added (+) or removed (-) project, and per migrated (~) project file.
+    pub summary: Vec<String>,
+    content: String,
+    /// Project files of days generated before the package versions were centralized.
+    migrations: Vec<(PathBuf, String)>,
+}
+
+impl CSharpSolutionSync {
+    /// Every file the sync may write.
+    pub fn paths(&self, root: &Path) -> Vec<PathBuf> {
+        let mut paths = vec![self.path.clone(), packages_props_path(root)];
+        paths.extend(self.migrations.iter().map(|(path, _)| path.clone()));
+        paths
+    }
+
+    pub fn apply(&self, root: &Path) -> Result<(), KataError> {
+        for (project_path, content) in &self.migrations {
+            fs::write(project_path, content).map_err(|e| {
+                KataError::io(format!("Failed to write {}", project_path.display()), e)
+            })?;
+        }
+
+        write_packages_props(root)?;
+        fs::write(&self.path, &self.content)
+            .map_err(|e| KataError::io(format!("Failed to write {}", self.path.display()), e))
+    }
+}
+
+/// Works out the solution projects, treating the `ignored_days` as already removed.
+/// Returns `None` when the solution is up to date, or when there are neither C# days nor a solution.
+pub fn plan_csharp_solution(
+    root: &Path,
+    ignored_days: &[PathBuf],
+) -> Result<Option<CSharpSolutionSync>, KataError> {
+    let csharp_dir = root.join(Language::CSharp.as_str());
+    let mut projects = Vec::new();
+    let mut migrations = Vec::new();
+    for kata in Kata::ALL {
+        for day_folder_path in list_day_folders(&csharp_dir.join(kata.as_str()))? {
+            if ignored_days.contains(&day_folder_path) {
+                continue;
+            }
+
+            let day = day_folder_path.file_name().unwrap().to_string_lossy();
+            let relative_path = format!("{}\\{}\\Kata.csproj", kata.as_str(), day);
+            let guid = project_guid(&relative_path);
+            projects.push((format!("{}_{}", kata.as_str(), day), relative_path, guid));
+
+            let project_path = day_folder_path.join("Kata.csproj");
+            let project = fs::read_to_string(&project_path).unwrap_or_default();
+            let migrated = centralize_package_versions(&project);
+            if migrated != project {
+                migrations.push((project_path, migrated));
+            }
+        }
+    }
+
+    let path = solution_path(root);
+    let existing = fs::read_to_string(&path).ok();
+    if existing.is_none() && projects.is_empty() {
+        return Ok(None);
+    }
+    if existing
+        .as_deref()
+        .is_some_and(|existing| !existing.contains(SOLUTION_MARKER))
+    {
+        return Err(KataError::InvalidInput(format!(
+            "{} already exists and was not generated by kata_machine.",
+            path.display()
+        )));
+    }
+
+    let members: Vec<String> = projects
+        .iter()
+        .map(|(_, relative_path, _)| project_day(relative_path))
+        .collect();
+    let content = generate_solution_file(SOLUTION_MARKER, &projects);
+
+    let previous_members: Vec<String> = existing
+        .as_deref()
+        .map(solution_projects)
+        .unwrap_or_default()
+        .iter()
+        .map(|relative_path| project_day(relative_path))
+        .collect();
+    let mut summary: Vec<String> = previous_members
+        .iter()
+        .filter(|member| !members.contains(member))
+        .map(|member| format!("- {}", member))
+        .collect();
+    summary.extend(
+        members
+            .iter()
+            .filter(|member| !previous_members.contains(member))
+            .map(|member| format!("+ {}", member)),
+    );
+    summary.extend(migrations.iter().map(|(project_path, _)| {
+        let relative_path = project_path
+            .strip_prefix(&csharp_dir)
+            .unwrap_or(project_path);
+        format!(
+            "~ {}: central package versions",
+            relative_path.to_string_lossy().replace('\\', "/")
+        )
+    }));
+
+    let props_up_to_date = fs::read_to_string(packages_props_path(root))
+        .is_ok_and(|props| props == generate_packages_props_file(SOLUTION_MARKER));
+    if existing.as_deref() == Some(content.as_str()) && migrations.is_empty() && props_up_to_date {
+        return Ok(None);
+    }
+
+    Ok(Some(CSharpSolutionSync {
+        path,
+        summary,
+        content,
+        migrations,
+    }))
+}
+
+/// A GUID derived from the project path, so regenerating the solution keeps the same ids.
+fn project_guid(relative_path: &str) -> String {
+    let hex = format!(
+        "{}{}",
+        content_hash(relative_path.as_bytes()),
+        content_hash(format!("{}#", relative_path).as_bytes())
+    )
+    .to_uppercase();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// `calculator\day1\Kata.csproj` as `calculator/day1`.
+fn project_day(relative_path: &str) -> String {
+    relative_path
+        .replace('\\', "/")
+        .trim_end_matches("/Kata.csproj")
+        .to_string()
+}
+
+/// The project paths of a solution, as `generate_solution_file` writes them.
+fn solution_projects(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter(|line| line.starts_with("Project("))
+        .filter_map(|line| line.split('"').nth(5))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Drops the versions the props file now pins from the package references, and turns the
+/// versions of packages the user added into overrides, which central versioning requires.
+fn centralize_package_versions(project: &str) -> String {
+    let mut migrated: Vec<String> = Vec::new();
+    for line in project.split('\n') {
+        if !line.contains("<PackageReference ") || !line.contains(" Version=\"") {
+            migrated.push(line.to_string());
+            continue;
+        }
+
+        let is_test_package = CSHARP_TEST_PACKAGES
+            .iter()
+            .any(|(name, _)| line.contains(&format!("Include=\"{}\"", name)));
+        if is_test_package {
+            let start = line.find(" Version=\"").unwrap();
+            let value_start = start + " Version=\"".len();
+            let end = line[value_start..]
+                .find('"')
+                .map_or(line.len(), |i| value_start + i + 1);
+            migrated.push(format!("{}{}", &line[..start], &line[end..]));
+        } else {
+            migrated.push(line.replacen(" Version=\"", " VersionOverride=\"", 1));
+        }
+    }
+    migrated.join("\n")
+}
@@ -1,3 +1,4 @@
 pub mod csharp;
 pub mod rust;
+pub mod test_report;
 pub mod typescript;
@@ -0,0 +1,157 @@
+use crate::{errors::KataError, list_day_folders, Kata, Language};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::generation::generate_rust_workspace_file;
+
+/// Marks the `rust/Cargo.toml` that kata_machine generated, which turns the workspace mode on.
+const WORKSPACE_MARKER: &str =
+    "Managed by kata_machine, `generate` and `clean` keep the members in sync with the day folders.";
+
+pub fn cargo_workspace_path(root: &Path) -> PathBuf {
+    root.join(Language::Rust.as_str()).join("Cargo.toml")
+}
+
+/// Whether the Rust days are members of one Cargo workspace sharing a `target/` folder.
+pub fn is_cargo_workspace(root: &Path) -> bool {
+    fs::read_to_string(cargo_workspace_path(root))
+        .map(|content| content.contains(WORKSPACE_MARKER))
+        .unwrap_or(false)
+}
+
+/// The changes needed to make the workspace members match the Rust day folders.
+pub struct CargoWorkspaceSync {
+    pub path: PathBuf,
+    /// A line per added (+) or removed (-) member, and per renamed (~) package.
+    pub summary: Vec<String>,
+    content: String,
+    /// Member manifests still using the package name every kata used to share.
+    renames: Vec<(PathBuf, String, String)>,
+}
+
+impl CargoWorkspaceSync {
+    /// Every file the sync may write.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        let mut paths = vec![self.path.clone()];
+        paths.extend(self.renames.iter().map(|(path, _, _)| path.clone()));
+        paths
+    }
+
+    pub fn apply(&self) -> Result<(), KataError> {
+        for (manifest_path, old_name, new_name) in &self.renames {
+            let manifest = fs::read_to_string(manifest_path).map_err(|e| {
+                KataError::io(format!("Failed to read {}", manifest_path.display()), e)
+            })?;
+            let manifest = manifest.replacen(
+                &format!("name = \"{}\"", old_name),
+                &format!("name = \"{}\"", new_name),
+                1,
+            );
+            fs::write(manifest_path, manifest).map_err(|e| {
+                KataError::io(format!("Failed to write {}", manifest_path.display()), e)
+            })?;
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| KataError::io(format!("Failed to create {}", parent.display()), e))?;
+        }
+        fs::write(&self.path, &self.content)
+            .map_err(|e| KataError::io(format!("Failed to write {}", self.path.display()), e))
+    }
+}
+
+/// Works out the workspace members, treating the `ignored_days` as already removed.
+/// Returns `None` when the workspace is up to date, or when `enable` is off and there is no workspace yet.
+pub fn plan_cargo_workspace(
+    root: &Path,
+    ignored_days: &[PathBuf],
+    enable: bool,
+) -> Result<Option<CargoWorkspaceSync>, KataError> {
+    if !enable && !is_cargo_workspace(root) {
+        return Ok(None);
+    }
+
+    let rust_dir = root.join(Language::Rust.as_str());
+    let mut members = Vec::new();
+    let mut renames = Vec::new();
+    for kata in Kata::ALL {
+        for day_folder_path in list_day_folders(&rust_dir.join(kata.as_str()))? {
+            if ignored_days.contains(&day_folder_path) {
+                continue;
+            }
+
+            let day = day_folder_path.file_name().unwrap().to_string_lossy();
+            members.push(format!("{}/{}", kata.as_str(), day));
+
+            // Days generated before the names included the kata clash with the calculator days
+            let old_name = format!("kata_machine_rust_calculator_{}", day);
+            let new_name = format!("kata_machine_rust_{}_{}", kata.as_str(), day);
+            let manifest_path = day_folder_path.join("Cargo.toml");
+            let manifest = fs::read_to_string(&manifest_path).unwrap_or_default();
+            if old_name != new_name && manifest.contains(&format!("name = \"{}\"", old_name)) {
+                renames.push((manifest_path, old_name, new_name));
+            }
+        }
+    }
+
+    let path = cargo_workspace_path(root);
+    let existing = fs::read_to_string(&path).ok();
+    if existing.is_some() && !is_cargo_workspace(root) {
+        return Err(KataError::InvalidInput(format!(
+            "{} already exists and was not generated by kata_machine.",
+            path.display()
+        )));
+    }
+
+    let content = generate_rust_workspace_file(WORKSPACE_MARKER, &members);
+    let previous_members = existing
+        .as_deref()
+        .map(workspace_members)
+        .unwrap_or_default();
+    let mut summary: Vec<String> = previous_members
+        .iter()
+        .filter(|member| !members.contains(member))
+        .map(|member| format!("- {}", member))
+        .collect();
+    summary.extend(
+        members
+            .iter()
+            .filter(|member| !previous_members.contains(member))
+            .map(|member| format!("+ {}", member)),
+    );
+    summary.extend(
+        renames
+            .iter()
+            .map(|(_, old_name, new_name)| format!("~ {} -> {}", old_name, new_name)),
+    );
+
+    if existing.as_deref() == Some(content.as_str()) && renames.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(CargoWorkspaceSync {
+        path,
+        summary,
+        content,
+        renames,
+    }))
+}
+
+/// The quoted entries of the `members` array, one per line as `generate_rust_workspace_file` writes them.
+fn workspace_members(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .skip_while(|line| !line.starts_with("members"))
+        .skip(1)
+        .take_while(|line| !line.starts_with(']'))
+        .map(|line| {
+            line.trim()
+                .trim_end_matches(',')
+                .trim_matches('"')
+                .to_string()
+        })
+        .collect()
+}
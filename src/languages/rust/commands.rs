@@ -1,35 +1,199 @@
-use crate::{file_utils::create_file_with_content, Kata};
+use crate::{
+    errors::KataError,
+    file_utils::create_file_with_content,
+    languages::test_report::*,
+    process_utils::{run, run_supervised, RunLimits},
+    Kata,
+};
 use std::{
-    path::Path,
-    process::{Command, Stdio},
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
 };
 
-use super::generation::*;
+use super::{generation::*, RustCrateOptions, RustDevDependency, NO_STD_ATTRIBUTE};
+
+pub use super::generation::generate_rust_calculator_reference;
 
-pub fn generate_rust_files(day_folder_path: &Path, kata: Kata) -> Result<(), String> {
-    let (main_file_content, test_file_content) = match kata {
+pub fn generate_rust_files(
+    day_folder_path: &Path,
+    kata: Kata,
+    options: &RustCrateOptions,
+) -> Result<(), KataError> {
+    let (mut main_file_content, mut test_file_content) = match kata {
         Kata::Calculator => generate_rust_calculator_files(),
         Kata::Dsa => generate_rust_dsa_files(),
         Kata::Blank => (String::new(), String::new()),
     };
+    if options.no_std {
+        main_file_content = format!("{}\n\n{}", NO_STD_ATTRIBUTE, main_file_content);
+    }
+    if options
+        .dev_dependencies
+        .contains(&RustDevDependency::PrettyAssertions)
+    {
+        test_file_content = test_file_content.replacen(
+            "use crate::add_numbers;",
+            "use crate::add_numbers;\n    use pretty_assertions::assert_eq;",
+            1,
+        );
+    }
 
     let day = day_folder_path.file_name().unwrap().to_string_lossy();
-    let cargo_file_content = generate_rust_cargo_file(&day);
+    let cargo_file_content = generate_rust_cargo_file(kata.as_str(), &day, options);
 
     create_file_with_content(day_folder_path, "lib.rs", &main_file_content)?;
     create_file_with_content(day_folder_path, "test.rs", &test_file_content)?;
     create_file_with_content(day_folder_path, "Cargo.toml", &cargo_file_content)?;
+    if let Some(toolchain) = &options.toolchain {
+        create_file_with_content(
+            day_folder_path,
+            "rust-toolchain.toml",
+            &generate_rust_toolchain_file(toolchain),
+        )?;
+    }
     Ok(())
 }
 
-pub fn run_rust_tests(day_folder_path: &Path) -> Result<(), String> {
-    Command::new("cargo")
-        .arg("test")
-        .current_dir(day_folder_path)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .output()
-        .map_err(|e| format!("Failed to execute Rust tests: {}", e))?;
+/// Replaces the tests of a copied calculator day with the hidden acceptance tests.
+pub fn write_rust_calculator_acceptance_tests(
+    day_folder_path: &Path,
+    cases: &[(&str, &str, i32)],
+) -> Result<(), KataError> {
+    create_file_with_content(
+        day_folder_path,
+        "test.rs",
+        &generate_rust_calculator_acceptance_tests(cases),
+    )?;
+    detach_from_cargo_workspace(day_folder_path)
+}
+
+/// Replaces the tests of a copied calculator day with the harness `fuzz` runs the inputs through.
+pub fn write_rust_calculator_fuzz_harness(
+    day_folder_path: &Path,
+    inputs_path: &Path,
+    outputs_path: &Path,
+) -> Result<(), KataError> {
+    create_file_with_content(
+        day_folder_path,
+        "test.rs",
+        &generate_rust_calculator_fuzz_harness(inputs_path, outputs_path),
+    )?;
+    detach_from_cargo_workspace(day_folder_path)
+}
+
+/// Adds a test of the case to the end of the tests module of the day, returning the file changed.
+pub fn append_rust_calculator_test_case(
+    day_folder_path: &Path,
+    case: (&str, &str),
+    expected: Option<i32>,
+) -> Result<PathBuf, KataError> {
+    let path = day_folder_path.join("test.rs");
+    let content = fs::read_to_string(&path)
+        .map_err(|e| KataError::io(format!("Failed to read {}", path.display()), e))?;
+    let module = content.trim_end().strip_suffix('}').ok_or_else(|| {
+        KataError::InvalidInput(format!(
+            "{} does not end with the tests module.",
+            path.display()
+        ))
+    })?;
 
+    let name = format!(
+        "fuzz_counter_example_{}",
+        content.matches("fn fuzz_counter_example_").count() + 1
+    );
+    let test = generate_rust_calculator_test_case(&name, case, expected);
+    fs::write(
+        &path,
+        format!("{}{}}}\n", module.trim_end_matches(' '), test),
+    )
+    .map_err(|e| KataError::io(format!("Failed to write {}", path.display()), e))?;
+    Ok(path)
+}
+
+/// Makes a copy of a day a workspace of its own, so a Cargo workspace of the days does not claim it.
+fn detach_from_cargo_workspace(day_folder_path: &Path) -> Result<(), KataError> {
+    let manifest_path = day_folder_path.join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path)
+        .map_err(|e| KataError::io(format!("Failed to read {}", manifest_path.display()), e))?;
+    if !manifest.contains("[workspace]") {
+        create_file_with_content(
+            day_folder_path,
+            "Cargo.toml",
+            &format!("{}\n[workspace]\n", manifest),
+        )?;
+    }
     Ok(())
 }
+
+/// The directory registry `init` vendors the supported dev-dependencies into.
+fn vendor_dir(root: &Path) -> PathBuf {
+    root.join(".kata").join("vendor")
+}
+
+/// Resolves the dev-dependencies of the day from the vendored registry, through a
+/// `.cargo/config.toml` in the day folder. Days without dev-dependencies are left alone.
+pub fn use_vendored_registry(root: &Path, day_folder_path: &Path) -> Result<(), KataError> {
+    if RustCrateOptions::of_day(day_folder_path)
+        .dev_dependencies
+        .is_empty()
+    {
+        return Ok(());
+    }
+
+    let vendor_dir = vendor_dir(root);
+    if !vendor_dir.is_dir() {
+        return Err(KataError::InvalidInput(format!(
+            "No vendored registry at {}, run `kata_machine init` while online first.",
+            vendor_dir.display()
+        )));
+    }
+
+    let cargo_dir = day_folder_path.join(".cargo");
+    fs::create_dir_all(&cargo_dir)
+        .map_err(|e| KataError::io(format!("Failed to create {}", cargo_dir.display()), e))?;
+    create_file_with_content(
+        &cargo_dir,
+        "config.toml",
+        &generate_cargo_config_file(&vendor_dir),
+    )
+}
+
+/// Vendors every supported dev-dependency into `.kata/vendor`, through a throwaway crate.
+pub fn seed_vendored_registry(root: &Path) -> Result<(), KataError> {
+    let seed_path = root.join(".kata").join("staging").join("vendor-seed");
+    fs::create_dir_all(seed_path.join("src"))
+        .map_err(|e| KataError::io(format!("Failed to create {}", seed_path.display()), e))?;
+    create_file_with_content(&seed_path, "Cargo.toml", &generate_vendor_seed_cargo_file())?;
+    create_file_with_content(&seed_path.join("src"), "lib.rs", "")?;
+
+    let vendored = run(Command::new("cargo")
+        .arg("vendor")
+        .arg(vendor_dir(root))
+        .current_dir(&seed_path));
+
+    let _ = fs::remove_dir_all(&seed_path);
+    vendored.map(|_| ())
+}
+
+pub fn run_rust_tests(day_folder_path: &Path, limits: &RunLimits) -> Result<TestReport, KataError> {
+    let (record, output, limit_exceeded) = run_supervised(
+        Command::new("cargo")
+            .arg("test")
+            .current_dir(day_folder_path),
+        limits,
+    )?;
+
+    // cargo prints a "test result: ok. 3 passed; 1 failed; ..." line per test target
+    let summary = output
+        .lines()
+        .filter(|line| line.trim_start().starts_with("test result:"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(TestReport {
+        success: record.status.success() && limit_exceeded.is_none(),
+        passed_tests: count_before(&summary, "passed"),
+        failed_tests: count_before(&summary, "failed"),
+        limit_exceeded,
+    })
+}
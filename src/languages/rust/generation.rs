@@ -1,3 +1,8 @@
+use super::{RustCrateOptions, RustDevDependency};
+use std::path::Path;
+
+const DEFAULT_EDITION: &str = "2021";
+
 pub fn generate_rust_calculator_files() -> (String, String) {
     let program_file_content = r#"mod test;
 
@@ -26,6 +31,95 @@ mod tests {
     (program_file_content, test_file_content)
 }
 
+/// The reference solution of the calculator, shown by `submit --reveal`.
+pub fn generate_rust_calculator_reference() -> String {
+    r#"mod test;
+
+pub fn add_numbers(num1: &str, num2: &str) -> i32 {
+    let parse = |num: &str| num.trim().parse::<i32>().expect("not a number");
+    parse(num1)
+        .checked_add(parse(num2))
+        .expect("the sum does not fit in an i32")
+}"#
+    .to_string()
+}
+
+/// The hidden calculator tests `submit` runs, one test per case so each one counts towards the score.
+pub fn generate_rust_calculator_acceptance_tests(cases: &[(&str, &str, i32)]) -> String {
+    let tests: String = cases
+        .iter()
+        .enumerate()
+        .map(|(index, (num1, num2, sum))| {
+            format!(
+                r#"
+    #[test]
+    fn acceptance_{}() {{
+        assert_eq!(add_numbers("{}", "{}"), {});
+    }}
+"#,
+                index + 1,
+                num1,
+                num2,
+                sum
+            )
+        })
+        .collect();
+
+    format!(
+        "#[cfg(test)]\nmod tests {{\n    use crate::add_numbers;\n{}}}\n",
+        tests
+    )
+}
+
+/// A test running the calculator on every `num1<TAB>num2` line of the inputs file, writing the
+/// sum or `error` for a panic as a line of the outputs file.
+pub fn generate_rust_calculator_fuzz_harness(inputs_path: &Path, outputs_path: &Path) -> String {
+    format!(
+        r#"#[cfg(test)]
+mod tests {{
+    use crate::add_numbers;
+
+    #[test]
+    fn fuzz_harness() {{
+        std::panic::set_hook(Box::new(|_| {{}}));
+        let inputs = std::fs::read_to_string({:?}).unwrap();
+        let outputs: Vec<String> = inputs
+            .lines()
+            .map(|line| {{
+                let (num1, num2) = line.split_once('\t').unwrap();
+                match std::panic::catch_unwind(|| add_numbers(num1, num2)) {{
+                    Ok(sum) => sum.to_string(),
+                    Err(_) => "error".to_string(),
+                }}
+            }})
+            .collect();
+        std::fs::write({:?}, outputs.join("\n")).unwrap();
+    }}
+}}
+"#,
+        inputs_path.display().to_string(),
+        outputs_path.display().to_string()
+    )
+}
+
+/// A test of the calculator for one case, expecting a panic when there is no sum.
+pub fn generate_rust_calculator_test_case(
+    name: &str,
+    (num1, num2): (&str, &str),
+    expected: Option<i32>,
+) -> String {
+    match expected {
+        Some(sum) => format!(
+            "\n    #[test]\n    fn {}() {{\n        assert_eq!(add_numbers({:?}, {:?}), {});\n    }}\n",
+            name, num1, num2, sum
+        ),
+        None => format!(
+            "\n    #[test]\n    #[should_panic]\n    fn {}() {{\n        add_numbers({:?}, {:?});\n    }}\n",
+            name, num1, num2
+        ),
+    }
+}
+
 pub fn generate_rust_dsa_files() -> (String, String) {
     let program_file_content = r#"
         // TODO
@@ -40,18 +134,87 @@ pub fn generate_rust_dsa_files() -> (String, String) {
     (program_file_content, test_file_content)
 }
 
-pub fn generate_rust_cargo_file(day: &str) -> String {
+pub fn generate_rust_cargo_file(kata: &str, day: &str, options: &RustCrateOptions) -> String {
+    // In a fixed order, and only once however often they were asked for
+    let dev_dependencies: String = RustDevDependency::ALL
+        .iter()
+        .filter(|dependency| options.dev_dependencies.contains(dependency))
+        .map(|dependency| format!("{} = \"{}\"\n", dependency.as_str(), dependency.version()))
+        .collect();
+    let dev_dependencies = match dev_dependencies.is_empty() {
+        true => dev_dependencies,
+        false => format!("\n[dev-dependencies]\n{}", dev_dependencies),
+    };
+
     format!(
         r#"[package]
-name = "kata_machine_rust_calculator_{}"
+name = "kata_machine_rust_{}_{}"
 version = "0.1.0"
-edition = "2021"
+edition = "{}"
 
 [dependencies]
-
+{}
 [lib]
 path = "lib.rs"
 "#,
+        kata,
         day,
+        options.edition.as_deref().unwrap_or(DEFAULT_EDITION),
+        dev_dependencies,
+    )
+}
+
+pub fn generate_rust_toolchain_file(channel: &str) -> String {
+    format!("[toolchain]\nchannel = \"{}\"\n", channel)
+}
+
+/// Points crates.io at the vendored registry, so the dev-dependencies resolve without the network.
+pub fn generate_cargo_config_file(vendor_dir: &Path) -> String {
+    format!(
+        r#"[source.crates-io]
+replace-with = "kata-vendor"
+
+[source.kata-vendor]
+directory = "{}"
+"#,
+        vendor_dir.display().to_string().replace('\\', "/")
+    )
+}
+
+/// A crate depending on every supported dev-dependency, vendored to fill the registry.
+pub fn generate_vendor_seed_cargo_file() -> String {
+    let dependencies: String = RustDevDependency::ALL
+        .iter()
+        .map(|dependency| format!("{} = \"{}\"\n", dependency.as_str(), dependency.version()))
+        .collect();
+
+    format!(
+        r#"[package]
+name = "kata_machine_vendor_seed"
+version = "0.1.0"
+edition = "{}"
+
+[dependencies]
+{}
+[workspace]
+"#,
+        DEFAULT_EDITION, dependencies,
+    )
+}
+
+pub fn generate_rust_workspace_file(marker: &str, members: &[String]) -> String {
+    let members: String = members
+        .iter()
+        .map(|member| format!("    \"{}\",\n", member))
+        .collect();
+
+    format!(
+        r#"# {}
+[workspace]
+resolver = "2"
+members = [
+{}]
+"#,
+        marker, members,
     )
 }
@@ -1,2 +1,178 @@
+pub mod cargo_workspace;
 pub mod commands;
 mod generation;
+
+use crate::install::Tool;
+use std::{fs, path::Path};
+use toml_edit::{DocumentMut, Item};
+
+/// The tools `init` installs for Rust days: rustc links with the C compiler.
+pub(crate) const REQUIRED_TOOLS: [Tool; 2] = [Tool::Rust, Tool::Linker];
+
+/// Heads the library of `no_std` days. The tests still link std, for the test harness.
+pub(crate) const NO_STD_ATTRIBUTE: &str = "#![cfg_attr(not(test), no_std)]";
+
+/// A crate the tests of a Rust day may use, resolved from the vendored registry `init` fills.
+#[derive(Clone, Copy, PartialEq)]
+pub enum RustDevDependency {
+    Proptest,
+    Rstest,
+    PrettyAssertions,
+}
+
+impl RustDevDependency {
+    pub const ALL: [RustDevDependency; 3] = [
+        RustDevDependency::Proptest,
+        RustDevDependency::Rstest,
+        RustDevDependency::PrettyAssertions,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RustDevDependency::Proptest => "proptest",
+            RustDevDependency::Rstest => "rstest",
+            RustDevDependency::PrettyAssertions => "pretty_assertions",
+        }
+    }
+
+    pub fn get(s: &str) -> Option<Self> {
+        RustDevDependency::ALL
+            .into_iter()
+            .find(|dependency| dependency.as_str() == s)
+    }
+
+    fn version(&self) -> &'static str {
+        match self {
+            RustDevDependency::Proptest => "1",
+            RustDevDependency::Rstest => "0.23",
+            RustDevDependency::PrettyAssertions => "1",
+        }
+    }
+}
+
+/// `stable`, `beta`, `nightly`, a dated channel like `nightly-2024-05-01`, or a version like `1.82.0`.
+pub fn is_valid_toolchain(toolchain: &str) -> bool {
+    let is_version = |version: &str| {
+        let parts: Vec<&str> = version.split('.').collect();
+        (2..=3).contains(&parts.len()) && parts.iter().all(|part| part.parse::<u32>().is_ok())
+    };
+    let channel = toolchain.split('-').next().unwrap_or(toolchain);
+    ["stable", "beta", "nightly"].contains(&channel) || is_version(channel)
+}
+
+pub fn is_valid_edition(edition: &str) -> bool {
+    ["2015", "2018", "2021", "2024"].contains(&edition)
+}
+
+/// How the crate of a Rust day is set up.
+#[derive(Clone, Default)]
+pub struct RustCrateOptions {
+    /// Writes a `rust-toolchain.toml` with this channel, e.g. `stable`, `nightly` or `1.82.0`.
+    pub toolchain: Option<String>,
+    /// Defaults to 2021.
+    pub edition: Option<String>,
+    pub dev_dependencies: Vec<RustDevDependency>,
+    /// Makes the library `no_std` outside of its tests.
+    pub no_std: bool,
+}
+
+impl RustCrateOptions {
+    /// The options an existing day was generated with, read from its files.
+    pub fn of_day(day_folder_path: &Path) -> Self {
+        let read = |name: &str| fs::read_to_string(day_folder_path.join(name)).unwrap_or_default();
+        let parse = |name: &str| read(name).parse::<DocumentMut>().unwrap_or_default();
+        let text = |item: Option<&Item>| item.and_then(Item::as_str).map(str::to_string);
+
+        let cargo_toml = parse("Cargo.toml");
+        let dev_dependencies = cargo_toml
+            .get("dev-dependencies")
+            .and_then(Item::as_table_like)
+            .map(|section| {
+                RustDevDependency::ALL
+                    .into_iter()
+                    .filter(|dependency| section.contains_key(dependency.as_str()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        RustCrateOptions {
+            toolchain: text(
+                parse("rust-toolchain.toml")
+                    .get("toolchain")
+                    .and_then(|toolchain| toolchain.get("channel")),
+            ),
+            edition: text(
+                cargo_toml
+                    .get("package")
+                    .and_then(|package| package.get("edition")),
+            ),
+            dev_dependencies,
+            no_std: read("lib.rs")
+                .lines()
+                .any(|line| line.trim() == NO_STD_ATTRIBUTE),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{write_template_files, Kata, Language, TemplateOptions};
+
+    fn day_folder(name: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("kata_machine_rust_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_back_the_options_a_day_was_generated_with() {
+        let day_folder_path = day_folder("generated");
+        let rust_crate = RustCrateOptions {
+            toolchain: Some("1.82.0".to_string()),
+            edition: Some("2018".to_string()),
+            dev_dependencies: vec![RustDevDependency::Rstest],
+            no_std: true,
+        };
+        let options = TemplateOptions {
+            rust_crate,
+            ..Default::default()
+        };
+        write_template_files(&day_folder_path, Language::Rust, Kata::Calculator, &options).unwrap();
+
+        let options = RustCrateOptions::of_day(&day_folder_path);
+        fs::remove_dir_all(&day_folder_path).unwrap();
+        assert_eq!(options.toolchain.as_deref(), Some("1.82.0"));
+        assert_eq!(options.edition.as_deref(), Some("2018"));
+        assert!(options.dev_dependencies == [RustDevDependency::Rstest]);
+        assert!(options.no_std);
+    }
+
+    #[test]
+    fn reads_reformatted_manifests() {
+        let day_folder_path = day_folder("reformatted");
+        fs::write(
+            day_folder_path.join("Cargo.toml"),
+            "[package]\nname = \"day1\"\nedition=\"2024\"\n\n[dev-dependencies.proptest]\nversion = \"1\"\n",
+        )
+        .unwrap();
+        fs::write(
+            day_folder_path.join("rust-toolchain.toml"),
+            "[toolchain]\nchannel    = 'nightly'\n",
+        )
+        .unwrap();
+        fs::write(
+            day_folder_path.join("lib.rs"),
+            "// Could be made no_std later\npub fn add() {}\n",
+        )
+        .unwrap();
+
+        let options = RustCrateOptions::of_day(&day_folder_path);
+        fs::remove_dir_all(&day_folder_path).unwrap();
+        assert_eq!(options.toolchain.as_deref(), Some("nightly"));
+        assert_eq!(options.edition.as_deref(), Some("2024"));
+        assert!(options.dev_dependencies == [RustDevDependency::Proptest]);
+        assert!(!options.no_std);
+    }
+}
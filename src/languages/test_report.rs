@@ -0,0 +1,39 @@
+use crate::process_utils::LimitExceeded;
+
+pub struct TestReport {
+    pub success: bool,
+    pub passed_tests: u32,
+    pub failed_tests: u32,
+    /// Set when the run was stopped for taking too long or using too much memory.
+    pub limit_exceeded: Option<LimitExceeded>,
+}
+
+/// Sums the numbers directly in front of `word` across the output, e.g. `3 passed`.
+pub fn count_before(output: &str, word: &str) -> u32 {
+    output
+        .lines()
+        .flat_map(|line| {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            words
+                .windows(2)
+                .filter(|pair| pair[1].trim_end_matches([',', ';', '.']) == word)
+                .filter_map(|pair| pair[0].parse::<u32>().ok())
+                .collect::<Vec<_>>()
+        })
+        .sum()
+}
+
+/// Sums the numbers directly after `label` across the output, e.g. `Passed: 3`.
+pub fn count_after(output: &str, label: &str) -> u32 {
+    output
+        .lines()
+        .flat_map(|line| {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            words
+                .windows(2)
+                .filter(|pair| pair[0] == label)
+                .filter_map(|pair| pair[1].trim_end_matches(',').parse::<u32>().ok())
+                .collect::<Vec<_>>()
+        })
+        .sum()
+}
@@ -1,76 +1,229 @@
-use crate::{file_utils::create_file_with_content, Kata};
+use crate::{
+    errors::KataError,
+    file_utils::create_file_with_content,
+    languages::test_report::*,
+    process_utils::{run, run_supervised, RunLimits},
+    Kata,
+};
 use std::{
     fs,
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    process::Command,
 };
 
-use super::generation::*;
+use super::{generation::*, TsRunner};
+
+pub use super::generation::generate_typescript_calculator_reference;
+
+const SHARED_PACKAGE_NAME: &str = "\"name\": \"kata_machine_typescript\"";
+
+/// Installs the test dependencies once into `typescript/node_modules`, which the day folders
+/// resolve them from. npm is skipped entirely when the ones the runners need are already
+/// installed, or when the runners need none.
+pub fn install_shared_typescript_dependencies(
+    typescript_dir: &Path,
+    runners: &[TsRunner],
+    offline: bool,
+) -> Result<(), KataError> {
+    let needed: Vec<&str> = runners
+        .iter()
+        .flat_map(|runner| runner.dev_dependencies())
+        .copied()
+        .collect();
+    if needed.is_empty() {
+        return Ok(());
+    }
+
+    let package_json = generate_typescript_shared_package_file();
+    let package_path = typescript_dir.join("package.json");
+    let existing = fs::read_to_string(&package_path).ok();
+
+    if let Some(existing) = &existing {
+        if !existing.contains(SHARED_PACKAGE_NAME) {
+            return Err(KataError::InvalidInput(format!(
+                "{} already exists and was not generated by kata_machine.",
+                package_path.display()
+            )));
+        }
+    }
+
+    let installed = needed.iter().all(|name| {
+        typescript_dir
+            .join("node_modules")
+            .join(name)
+            .join("package.json")
+            .exists()
+    });
+    if installed && existing.is_some() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(typescript_dir)
+        .map_err(|e| KataError::io(format!("Failed to create {}", typescript_dir.display()), e))?;
+    create_file_with_content(typescript_dir, "package.json", &package_json)?;
+
+    let mut command = Command::new("npm");
+    command.arg("install").current_dir(typescript_dir);
+    if offline {
+        // Only use the npm cache, failing right away instead of waiting on the network
+        command.arg("--offline");
+    }
+    run(&mut command)?;
 
-pub fn generate_typescript_files(day_folder_path: &PathBuf, kata: Kata) -> Result<(), String> {
+    Ok(())
+}
+
+pub fn write_typescript_template_files(
+    day_folder_path: &Path,
+    kata: Kata,
+    runner: TsRunner,
+) -> Result<(), KataError> {
     let src_folder_path = day_folder_path.join("src");
     if !src_folder_path.exists() {
         fs::create_dir(&src_folder_path)
-            .map_err(|e| format!("Failed to create src directory: {}", e))?;
+            .map_err(|e| KataError::io("Failed to create src directory", e))?;
     };
 
-    match kata {
-        Kata::Calculator => generate_typescript_calculator(&src_folder_path)?,
-        Kata::Dsa => generate_typescript_dsa(&src_folder_path)?,
-        Kata::Blank => generate_typescript_blank(&src_folder_path)?,
+    let (module, (program_file_content, test_file_content)) = match kata {
+        Kata::Calculator => ("calculator", generate_typescript_calculator_files(runner)),
+        Kata::Dsa => ("dsa", generate_typescript_dsa_files()),
+        Kata::Blank => ("solution", generate_typescript_blank_files(runner)),
     };
-
-    let (package_json, jest_config, ts_config) = generate_typescript_config_files();
-    create_file_with_content(day_folder_path, "package.json", &package_json)?;
-    create_file_with_content(day_folder_path, "jest.config.js", &jest_config)?;
-    create_file_with_content(day_folder_path, "tsconfig.json", &ts_config)?;
-
-    Command::new("npm")
-        .arg("i")
-        .current_dir(day_folder_path)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .output()
-        .map_err(|e| format!("Failed to install dependencies: {}", e))?;
+    create_file_with_content(
+        &src_folder_path,
+        &format!("{}.ts", module),
+        &program_file_content,
+    )?;
+    create_file_with_content(
+        &src_folder_path,
+        &format!("{}{}", module, runner.test_file_suffix()),
+        &test_file_content,
+    )?;
+
+    for (file_name, content) in generate_typescript_config_files(runner) {
+        create_file_with_content(day_folder_path, file_name, &content)?;
+    }
 
     Ok(())
 }
 
-fn generate_typescript_calculator(src_folder_path: &Path) -> Result<(), String> {
-    let (program_file_content, test_file_content) = generate_typescript_calculator_files();
-
-    create_file_with_content(src_folder_path, "calculator.ts", &program_file_content)?;
-    create_file_with_content(src_folder_path, "calculator.spec.ts", &test_file_content)?;
-
-    Ok(())
+/// Writes the hidden acceptance tests next to the calculator of a copied day, for its runner.
+pub fn write_typescript_calculator_acceptance_tests(
+    day_folder_path: &Path,
+    cases: &[(&str, &str, i32)],
+) -> Result<(), KataError> {
+    let runner = TsRunner::of_day(day_folder_path);
+    create_file_with_content(
+        &day_folder_path.join("src"),
+        &format!("calculator{}", runner.test_file_suffix()),
+        &generate_typescript_calculator_acceptance_tests(runner, cases),
+    )
 }
 
-fn generate_typescript_dsa(src_folder_path: &Path) -> Result<(), String> {
-    let (program_file_content, test_file_content) = generate_typescript_dsa_files();
-
-    create_file_with_content(src_folder_path, "dsa.ts", &program_file_content)?;
-    create_file_with_content(src_folder_path, "dsa.spec.ts", &test_file_content)?;
-
-    Ok(())
+/// Replaces the tests of a copied calculator day with the harness `fuzz` runs the inputs through.
+pub fn write_typescript_calculator_fuzz_harness(
+    day_folder_path: &Path,
+    inputs_path: &Path,
+    outputs_path: &Path,
+) -> Result<(), KataError> {
+    let runner = TsRunner::of_day(day_folder_path);
+    create_file_with_content(
+        &day_folder_path.join("src"),
+        &format!("calculator{}", runner.test_file_suffix()),
+        &generate_typescript_calculator_fuzz_harness(runner, inputs_path, outputs_path),
+    )
 }
 
-fn generate_typescript_blank(src_folder_path: &Path) -> Result<(), String> {
-    let (program_file_content, test_file_content) = generate_typescript_blank_files();
-
-    create_file_with_content(src_folder_path, "solution.ts", &program_file_content)?;
-    create_file_with_content(src_folder_path, "solution.spec.ts", &test_file_content)?;
-
-    Ok(())
+/// Adds a test of the case to the end of the calculator tests of the day, returning the file changed.
+pub fn append_typescript_calculator_test_case(
+    day_folder_path: &Path,
+    case: (&str, &str),
+    expected: Option<i32>,
+) -> Result<PathBuf, KataError> {
+    let runner = TsRunner::of_day(day_folder_path);
+    let path = day_folder_path
+        .join("src")
+        .join(format!("calculator{}", runner.test_file_suffix()));
+    let content = fs::read_to_string(&path)
+        .map_err(|e| KataError::io(format!("Failed to read {}", path.display()), e))?;
+
+    let name = format!(
+        "fuzz counter-example {}",
+        content.matches("fuzz counter-example ").count() + 1
+    );
+    let test = generate_typescript_calculator_test_case(runner, &name, case, expected);
+    fs::write(&path, format!("{}\n{}", content.trim_end(), test))
+        .map_err(|e| KataError::io(format!("Failed to write {}", path.display()), e))?;
+    Ok(path)
 }
 
-pub fn run_typescript_tests(day_folder_path: &Path) -> Result<(), String> {
-    Command::new("npm")
-        .arg("test")
-        .current_dir(day_folder_path)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .output()
-        .map_err(|e| format!("Failed to run TypeScript tests: {}", e))?;
+pub fn run_typescript_tests(
+    day_folder_path: &Path,
+    limits: &RunLimits,
+) -> Result<TestReport, KataError> {
+    let runner = TsRunner::of_day(day_folder_path);
+    let [program, argument] = runner.test_command();
+    let (record, output, limit_exceeded) = run_supervised(
+        Command::new(program)
+            .arg(argument)
+            .current_dir(day_folder_path),
+        limits,
+    )?;
+
+    let summary_lines = |is_summary: &dyn Fn(&str) -> bool| {
+        output
+            .lines()
+            .map(str::trim)
+            .filter(|line| is_summary(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let (passed_tests, failed_tests) = match runner {
+        // jest summarizes with "Tests: 1 failed, 2 passed, 3 total", vitest with "Tests  1 failed | 2 passed (3)"
+        TsRunner::Jest | TsRunner::Vitest => {
+            let summary = summary_lines(&|line| line.starts_with("Tests"));
+            (
+                count_before(&summary, "passed"),
+                count_before(&summary, "failed"),
+            )
+        }
+        // node:test ends with "# pass 2" and "# fail 1", or "ℹ pass 2" with the spec reporter
+        TsRunner::NodeTest => {
+            let summary = summary_lines(&|line| {
+                ["# pass", "# fail", "ℹ pass", "ℹ fail"]
+                    .iter()
+                    .any(|prefix| line.starts_with(prefix))
+            });
+            (count_after(&summary, "pass"), count_after(&summary, "fail"))
+        }
+        // deno summarizes with "FAILED | 2 passed | 1 failed (5ms)"
+        TsRunner::Deno => {
+            let summary =
+                summary_lines(&|line| line.starts_with("ok |") || line.starts_with("FAILED |"));
+            (
+                count_before(&summary, "passed"),
+                count_before(&summary, "failed"),
+            )
+        }
+        // bun ends with " 2 pass" and " 1 fail" lines
+        TsRunner::Bun => {
+            let summary = summary_lines(&|line| {
+                matches!(
+                    line.split_whitespace().collect::<Vec<_>>().as_slice(),
+                    [_, "pass" | "fail"]
+                )
+            });
+            (
+                count_before(&summary, "pass"),
+                count_before(&summary, "fail"),
+            )
+        }
+    };
 
-    Ok(())
+    Ok(TestReport {
+        success: record.status.success() && limit_exceeded.is_none(),
+        passed_tests,
+        failed_tests,
+        limit_exceeded,
+    })
 }
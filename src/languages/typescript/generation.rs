@@ -1,46 +1,248 @@
-pub fn generate_typescript_config_files() -> (String, String, String) {
-    let package_json = r#"{
-    "name": "kata",
-    "version": "1.0.0",
-    "scripts": {
-        "test": "jest"
-    },
-    "devDependencies": {
-        "@jest/globals": "^29.5.0",
-        "jest": "^29.5.0",
-        "ts-jest": "^29.1.0",
-        "typescript": "^5.0.4"
-    }
-}"#
-    .trim()
-    .to_string();
+use super::TsRunner;
+use std::path::Path;
+
+/// The packages of every npm-based runner, installed together into the shared `typescript/node_modules`.
+pub const TYPESCRIPT_DEV_DEPENDENCIES: [(&str, &str); 5] = [
+    ("@jest/globals", "^29.5.0"),
+    ("jest", "^29.5.0"),
+    ("ts-jest", "^29.1.0"),
+    ("typescript", "^5.0.4"),
+    ("vitest", "^2.1.0"),
+];
+
+fn generate_dev_dependencies(names: &[&str]) -> String {
+    TYPESCRIPT_DEV_DEPENDENCIES
+        .iter()
+        .filter(|(name, _)| names.contains(name))
+        .map(|(name, version)| format!("        \"{}\": \"{}\"", name, version))
+        .collect::<Vec<_>>()
+        .join(",\n")
+}
 
-    let jest_config = r#"module.exports = {
+/// The package.json of the `typescript` folder, whose node_modules every day resolves its test dependencies from.
+pub fn generate_typescript_shared_package_file() -> String {
+    let names: Vec<&str> = TYPESCRIPT_DEV_DEPENDENCIES
+        .iter()
+        .map(|(name, _)| *name)
+        .collect();
+
+    format!(
+        r#"{{
+    "name": "kata_machine_typescript",
+    "private": true,
+    "description": "Test dependencies shared by every TypeScript day, managed by kata_machine",
+    "devDependencies": {{
+{}
+    }}
+}}
+"#,
+        generate_dev_dependencies(&names)
+    )
+}
+
+/// The config files of a day, by file name.
+pub fn generate_typescript_config_files(runner: TsRunner) -> Vec<(&'static str, String)> {
+    match runner {
+        TsRunner::Jest => {
+            let jest_config = r#"module.exports = {
     transform: { '^.+\\.ts?$': 'ts-jest' },
     testEnvironment: 'node',
     testRegex: '[a-z]+.spec.ts',
     moduleFileExtensions: ['ts', 'tsx', 'js', 'jsx', 'json', 'node']
 };"#
-    .trim()
-    .to_string();
+            .trim()
+            .to_string();
+
+            vec![
+                ("package.json", generate_package_file(runner, None, "jest")),
+                ("jest.config.js", jest_config),
+                (
+                    "tsconfig.json",
+                    generate_ts_config(&[("target", "es2016"), ("module", "commonjs")]),
+                ),
+            ]
+        }
+        TsRunner::Vitest => {
+            let vitest_config = r#"import { defineConfig } from 'vitest/config';
+
+export default defineConfig({
+    test: {
+        include: ['src/**/*.spec.ts'],
+    },
+});"#
+                .to_string();
 
-    let ts_config = r#"{
-    "compilerOptions": {
-      "target": "es2016",
-      "module": "commonjs",
+            vec![
+                (
+                    "package.json",
+                    generate_package_file(runner, Some("module"), "vitest run"),
+                ),
+                ("vitest.config.ts", vitest_config),
+                ("tsconfig.json", generate_bundler_ts_config()),
+            ]
+        }
+        TsRunner::NodeTest => vec![
+            (
+                "package.json",
+                generate_package_file(
+                    runner,
+                    Some("module"),
+                    r#"node --experimental-strip-types --test \"src/**/*.spec.ts\""#,
+                ),
+            ),
+            (
+                "tsconfig.json",
+                generate_ts_config(&[
+                    ("target", "esnext"),
+                    ("module", "nodenext"),
+                    ("allowImportingTsExtensions", "true"),
+                    ("noEmit", "true"),
+                ]),
+            ),
+        ],
+        // deno finds the `*.test.ts` files itself and reads no package.json or tsconfig
+        TsRunner::Deno => vec![(
+            "deno.json",
+            r#"{
+    "test": {
+        "include": ["src/"]
+    }
+}"#
+            .to_string(),
+        )],
+        TsRunner::Bun => vec![
+            (
+                "package.json",
+                generate_package_file(runner, None, "bun test"),
+            ),
+            ("tsconfig.json", generate_bundler_ts_config()),
+        ],
+    }
+}
+
+fn generate_package_file(runner: TsRunner, module_type: Option<&str>, test_script: &str) -> String {
+    let module_type = module_type
+        .map(|module_type| format!("\n    \"type\": \"{}\",", module_type))
+        .unwrap_or_default();
+    let dev_dependencies = match runner.dev_dependencies() {
+        [] => String::new(),
+        names => format!(
+            ",\n    \"devDependencies\": {{\n{}\n    }}",
+            generate_dev_dependencies(names)
+        ),
+    };
+
+    format!(
+        r#"{{
+    "name": "kata",
+    "version": "1.0.0",{}
+    "scripts": {{
+        "test": "{}"
+    }}{}
+}}"#,
+        module_type, test_script, dev_dependencies
+    )
+}
+
+fn generate_bundler_ts_config() -> String {
+    generate_ts_config(&[
+        ("target", "es2022"),
+        ("module", "esnext"),
+        ("moduleResolution", "bundler"),
+    ])
+}
+
+/// A tsconfig.json with the given compiler options in front of the ones every runner shares.
+fn generate_ts_config(options: &[(&str, &str)]) -> String {
+    let options: Vec<String> = options
+        .iter()
+        .map(|(name, value)| match *value {
+            "true" | "false" => format!("      \"{}\": {}", name, value),
+            _ => format!("      \"{}\": \"{}\"", name, value),
+        })
+        .collect();
+
+    format!(
+        r#"{{
+    "compilerOptions": {{
+{},
       "esModuleInterop": true,
       "forceConsistentCasingInFileNames": true,
       "strict": true,
       "skipLibCheck": true
+    }}
+}}"#,
+        options.join(",\n")
+    )
+}
+
+/// The imports of a test file: the kata's export and the runner's test functions.
+fn generate_test_imports(runner: TsRunner, module: &str, export: &str) -> String {
+    match runner {
+        TsRunner::Jest => format!(
+            "import {{ {} }} from './{}';\nimport {{ describe, it, expect }} from '@jest/globals';",
+            export, module
+        ),
+        TsRunner::Vitest => format!(
+            "import {{ {} }} from './{}';\nimport {{ describe, it, expect }} from 'vitest';",
+            export, module
+        ),
+        TsRunner::Bun => format!(
+            "import {{ {} }} from './{}';\nimport {{ describe, it, expect }} from 'bun:test';",
+            export, module
+        ),
+        // Type stripping runs the files as they are, so imports need the extension
+        TsRunner::NodeTest => format!(
+            "import {{ {} }} from './{}.ts';\nimport {{ describe, it }} from 'node:test';\nimport assert from 'node:assert/strict';",
+            export, module
+        ),
+        TsRunner::Deno => format!(
+            "import {{ {} }} from './{}.ts';\nimport assert from 'node:assert/strict';",
+            export, module
+        ),
     }
-}"#
-    .trim()
-    .to_string();
+}
+
+/// The test of a kata for the runner: `expect_body` for the runners with a jest-style `expect`,
+/// `assert_body` for node:test and deno, which use `node:assert`.
+fn generate_test_file(
+    runner: TsRunner,
+    (module, export): (&str, &str),
+    (suite, case): (&str, &str),
+    expect_body: &str,
+    assert_body: &str,
+) -> String {
+    let imports = generate_test_imports(runner, module, export);
+    let body = match runner {
+        TsRunner::NodeTest | TsRunner::Deno => assert_body,
+        TsRunner::Jest | TsRunner::Vitest | TsRunner::Bun => expect_body,
+    };
+
+    match runner {
+        TsRunner::Deno => format!(
+            r#"{}
+
+Deno.test('{} {}', () => {{
+    {}
+    // Add more test cases here
+}});"#,
+            imports, suite, case, body
+        ),
+        _ => format!(
+            r#"{}
 
-    (package_json, jest_config, ts_config)
+describe('{}', () => {{
+    it('{}', () => {{
+        {}
+        // Add more test cases here
+    }});
+}});"#,
+            imports, suite, case, body
+        ),
+    }
 }
 
-pub fn generate_typescript_calculator_files() -> (String, String) {
+pub fn generate_typescript_calculator_files(runner: TsRunner) -> (String, String) {
     let program_file_content = r#"export function addNumbers(num1: string, num2: string): number {
     // Implement the addition logic here
     // ...
@@ -48,21 +250,147 @@ pub fn generate_typescript_calculator_files() -> (String, String) {
     .trim()
     .to_string();
 
-    let test_file_content = r#"import { addNumbers } from './calculator';
-import { describe, it, expect } from '@jest/globals';
-
-describe('Calculator', () => {
-    it('should add numbers correctly', () => {
-        expect(addNumbers("1", "2")).toEqual(3);
-        // Add more test cases here
-    });
-});"#
-        .trim()
-        .to_string();
+    let test_file_content = generate_test_file(
+        runner,
+        ("calculator", "addNumbers"),
+        ("Calculator", "should add numbers correctly"),
+        r#"expect(addNumbers("1", "2")).toEqual(3);"#,
+        r#"assert.equal(addNumbers("1", "2"), 3);"#,
+    );
 
     (program_file_content, test_file_content)
 }
 
+/// The reference solution of the calculator, shown by `submit --reveal`.
+pub fn generate_typescript_calculator_reference() -> String {
+    r#"const MIN_INT32 = -2147483648;
+const MAX_INT32 = 2147483647;
+
+function parseInt32(num: string): number {
+    const value = Number(num);
+    if (!/^\s*[+-]?\d+\s*$/.test(num) || value < MIN_INT32 || value > MAX_INT32) {
+        throw new RangeError(`not a 32-bit integer: '${num}'`);
+    }
+    return value;
+}
+
+export function addNumbers(num1: string, num2: string): number {
+    const sum = parseInt32(num1) + parseInt32(num2);
+    if (sum < MIN_INT32 || sum > MAX_INT32) {
+        throw new RangeError('the sum does not fit in 32 bits');
+    }
+    return sum;
+}"#
+    .to_string()
+}
+
+/// The hidden calculator tests `submit` runs, one test per case so each one counts towards the score.
+pub fn generate_typescript_calculator_acceptance_tests(
+    runner: TsRunner,
+    cases: &[(&str, &str, i32)],
+) -> String {
+    let tests: String = cases
+        .iter()
+        .map(|(num1, num2, sum)| {
+            let name = format!("adds {} and {}", num1, num2);
+            match runner {
+                TsRunner::Deno => format!(
+                    "\nDeno.test('Calculator {}', () => {{\n    assert.equal(addNumbers(\"{}\", \"{}\"), {});\n}});\n",
+                    name, num1, num2, sum
+                ),
+                TsRunner::NodeTest => format!(
+                    "    it('{}', () => {{\n        assert.equal(addNumbers(\"{}\", \"{}\"), {});\n    }});\n",
+                    name, num1, num2, sum
+                ),
+                TsRunner::Jest | TsRunner::Vitest | TsRunner::Bun => format!(
+                    "    it('{}', () => {{\n        expect(addNumbers(\"{}\", \"{}\")).toEqual({});\n    }});\n",
+                    name, num1, num2, sum
+                ),
+            }
+        })
+        .collect();
+
+    let imports = generate_test_imports(runner, "calculator", "addNumbers");
+    match runner {
+        TsRunner::Deno => format!("{}\n{}", imports, tests),
+        _ => format!(
+            "{}\n\ndescribe('Calculator acceptance', () => {{\n{}}});\n",
+            imports, tests
+        ),
+    }
+}
+
+/// A test running the calculator on every `num1<TAB>num2` line of the inputs file, writing the
+/// sum or `error` for an exception as a line of the outputs file.
+pub fn generate_typescript_calculator_fuzz_harness(
+    runner: TsRunner,
+    inputs_path: &Path,
+    outputs_path: &Path,
+) -> String {
+    let run = format!(
+        r#"const run = () => {{
+    const inputs = readFileSync({}, 'utf8').split('\n').filter((line) => line !== '');
+    const outputs = inputs.map((line) => {{
+        const [num1, num2] = line.split('\t');
+        try {{
+            return String(addNumbers(num1, num2));
+        }} catch {{
+            return 'error';
+        }}
+    }});
+    writeFileSync({}, outputs.join('\n'));
+}};"#,
+        string_literal(&inputs_path.display().to_string()),
+        string_literal(&outputs_path.display().to_string())
+    );
+    let test = match runner {
+        TsRunner::Deno => "Deno.test('Calculator fuzz harness', run);".to_string(),
+        _ => {
+            "describe('Calculator', () => {\n    it('runs the fuzz inputs', run);\n});".to_string()
+        }
+    };
+
+    // The days do not install the types of node:fs
+    format!(
+        "// @ts-nocheck\n{}\nimport {{ readFileSync, writeFileSync }} from 'node:fs';\n\n{}\n\n{}\n",
+        generate_test_imports(runner, "calculator", "addNumbers"),
+        run,
+        test
+    )
+}
+
+/// A test of the calculator for one case, expecting an exception when there is no sum.
+pub fn generate_typescript_calculator_test_case(
+    runner: TsRunner,
+    name: &str,
+    (num1, num2): (&str, &str),
+    expected: Option<i32>,
+) -> String {
+    let call = format!(
+        "addNumbers({}, {})",
+        string_literal(num1),
+        string_literal(num2)
+    );
+    let body = match (runner, expected) {
+        (TsRunner::NodeTest | TsRunner::Deno, Some(sum)) => {
+            format!("assert.equal({}, {});", call, sum)
+        }
+        (TsRunner::NodeTest | TsRunner::Deno, None) => format!("assert.throws(() => {});", call),
+        (_, Some(sum)) => format!("expect({}).toEqual({});", call, sum),
+        (_, None) => format!("expect(() => {}).toThrow();", call),
+    };
+
+    match runner {
+        TsRunner::Deno => format!("\nDeno.test('{}', () => {{\n    {}\n}});\n", name, body),
+        _ => format!("\nit('{}', () => {{\n    {}\n}});\n", name, body),
+    }
+}
+
+/// The text as a double-quoted string literal, which JSON and TypeScript write alike.
+fn string_literal(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
 pub fn generate_typescript_dsa_files() -> (String, String) {
     let program_file_content = r#"
         // TODO
@@ -77,7 +405,7 @@ pub fn generate_typescript_dsa_files() -> (String, String) {
     (program_file_content, test_file_content)
 }
 
-pub fn generate_typescript_blank_files() -> (String, String) {
+pub fn generate_typescript_blank_files(runner: TsRunner) -> (String, String) {
     let program_file_content = r#"export function solution() {
     // Implement the solution logic here
     // ...
@@ -85,17 +413,13 @@ pub fn generate_typescript_blank_files() -> (String, String) {
     .trim()
     .to_string();
 
-    let test_file_content = r#"import { solution } from './solution';
-import { describe, it, expect } from '@jest/globals';
-
-describe('Solution', () => {
-    it('should be callable', () => {
-        expect(() => solution()).not.toThrow();
-        // Add more test cases here
-    });
-});"#
-        .trim()
-        .to_string();
+    let test_file_content = generate_test_file(
+        runner,
+        ("solution", "solution"),
+        ("Solution", "should be callable"),
+        "expect(() => solution()).not.toThrow();",
+        "assert.doesNotThrow(() => solution());",
+    );
 
     (program_file_content, test_file_content)
 }
@@ -1,2 +1,96 @@
 pub mod commands;
 mod generation;
+
+use crate::install::Tool;
+use std::{fs, path::Path};
+
+/// The tools `init` installs for TypeScript days. Deno and bun are left to the user.
+pub(crate) const REQUIRED_TOOLS: [Tool; 1] = [Tool::Node];
+
+/// The tool that runs the tests of a TypeScript day.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum TsRunner {
+    #[default]
+    Jest,
+    Vitest,
+    /// Node's built-in `node:test`, running the TypeScript files with `--experimental-strip-types`.
+    NodeTest,
+    Deno,
+    Bun,
+}
+
+impl TsRunner {
+    pub const ALL: [TsRunner; 5] = [
+        TsRunner::Jest,
+        TsRunner::Vitest,
+        TsRunner::NodeTest,
+        TsRunner::Deno,
+        TsRunner::Bun,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TsRunner::Jest => "jest",
+            TsRunner::Vitest => "vitest",
+            TsRunner::NodeTest => "node",
+            TsRunner::Deno => "deno",
+            TsRunner::Bun => "bun",
+        }
+    }
+
+    pub fn get(s: &str) -> Option<Self> {
+        match s {
+            "jest" => Some(TsRunner::Jest),
+            "vitest" => Some(TsRunner::Vitest),
+            "node" | "node:test" => Some(TsRunner::NodeTest),
+            "deno" => Some(TsRunner::Deno),
+            "bun" => Some(TsRunner::Bun),
+            _ => None,
+        }
+    }
+
+    /// The runner a day was generated for, told apart by its config files.
+    pub fn of_day(day_folder_path: &Path) -> Self {
+        if day_folder_path.join("deno.json").exists() {
+            return TsRunner::Deno;
+        }
+
+        let package_json =
+            fs::read_to_string(day_folder_path.join("package.json")).unwrap_or_default();
+        if package_json.contains("\"vitest") {
+            TsRunner::Vitest
+        } else if package_json.contains("\"bun test\"") {
+            TsRunner::Bun
+        } else if package_json.contains("\"node ") {
+            TsRunner::NodeTest
+        } else {
+            TsRunner::Jest
+        }
+    }
+
+    /// The command running the tests in the day folder, program first.
+    pub fn test_command(&self) -> [&'static str; 2] {
+        match self {
+            TsRunner::Jest | TsRunner::Vitest | TsRunner::NodeTest => ["npm", "test"],
+            TsRunner::Deno => ["deno", "test"],
+            TsRunner::Bun => ["bun", "test"],
+        }
+    }
+
+    /// deno only picks up `*.test.ts` files, the others are happy with the `*.spec.ts` jest uses.
+    fn test_file_suffix(&self) -> &'static str {
+        match self {
+            TsRunner::Deno => ".test.ts",
+            _ => ".spec.ts",
+        }
+    }
+
+    /// The npm packages the runner needs in the shared `typescript/node_modules`.
+    pub(crate) fn dev_dependencies(&self) -> &'static [&'static str] {
+        match self {
+            TsRunner::Jest => &["@jest/globals", "jest", "ts-jest", "typescript"],
+            TsRunner::Vitest => &["vitest"],
+            TsRunner::NodeTest | TsRunner::Deno | TsRunner::Bun => &[],
+        }
+    }
+}
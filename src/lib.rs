@@ -1,17 +1,56 @@
+mod clean;
+mod compare;
+mod config;
+mod console_utils;
+mod diff;
+mod doctor;
+mod editor;
+mod errors;
 mod file_utils;
+mod fuzz;
+mod history;
+mod install;
 mod languages;
+mod pair;
+mod process_utils;
+mod review;
+mod submit;
+mod time_utils;
+mod workspace;
+
+pub use clean::CleanOptions;
+pub use compare::DiffFormat;
+pub use config::{
+    set_value, user_config_path, workspace_config_path, Config, ConfigLayer, ConfigValue,
+};
+pub use editor::Editor;
+pub use errors::KataError;
+pub use fuzz::FuzzOptions;
+pub use install::InitOptions;
+pub use languages::{
+    csharp::{is_valid_target_framework, CSharpTestFramework},
+    rust::{is_valid_edition, is_valid_toolchain, RustCrateOptions, RustDevDependency},
+    typescript::TsRunner,
+};
+pub use pair::{PairMode, PairPhase, PairTurn};
+pub use process_utils::{
+    kill_supervised_groups, kill_supervised_groups_on_signals, set_verbose, LimitExceeded,
+    RunLimits,
+};
+pub use time_utils::parse_timeout;
+pub use workspace::{GenerateOptions, Workspace};
 
-use languages::{csharp::commands::*, rust::commands::*, typescript::commands::*};
+use install::Tool;
+use languages::{
+    csharp::commands::*, rust::commands::*, test_report::TestReport, typescript::commands::*,
+};
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
 use std::{
-    fs::{self, File},
-    io::{Read, Write},
+    fs,
     path::{Path, PathBuf},
-    process::{Command, Stdio},
 };
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum Language {
     Rust,
     TS,
@@ -19,6 +58,8 @@ pub enum Language {
 }
 
 impl Language {
+    const ALL: [Language; 3] = [Language::Rust, Language::CSharp, Language::TS];
+
     fn as_str(&self) -> &'static str {
         match self {
             Language::CSharp => "csharp",
@@ -27,6 +68,39 @@ impl Language {
         }
     }
 
+    /// The tools each backend needs, which `init` installs.
+    fn required_tools(&self) -> &'static [Tool] {
+        match self {
+            Language::Rust => &languages::rust::REQUIRED_TOOLS,
+            Language::TS => &languages::typescript::REQUIRED_TOOLS,
+            Language::CSharp => &languages::csharp::REQUIRED_TOOLS,
+        }
+    }
+
+    fn file_role(&self, relative_path: &str) -> FileRole {
+        let file_name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+        let (is_test, is_source) = match self {
+            Language::Rust => (
+                file_name == "test.rs" || relative_path.starts_with("tests/"),
+                file_name.ends_with(".rs"),
+            ),
+            Language::TS => (
+                file_name.ends_with(".spec.ts") || file_name.ends_with(".test.ts"),
+                file_name.ends_with(".ts") && !file_name.ends_with(".config.ts"),
+            ),
+            Language::CSharp => (
+                file_name == "Test.cs" || file_name.ends_with("Tests.cs"),
+                file_name.ends_with(".cs"),
+            ),
+        };
+
+        match (is_test, is_source) {
+            (true, _) => FileRole::Test,
+            (false, true) => FileRole::Solution,
+            _ => FileRole::Config,
+        }
+    }
+
     pub fn get(s: &str) -> Option<Self> {
         match s {
             "cs" => Some(Language::CSharp),
@@ -39,7 +113,14 @@ impl Language {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
+enum FileRole {
+    Solution,
+    Test,
+    Config,
+}
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum Kata {
     Calculator,
     Dsa,
@@ -47,11 +128,13 @@ pub enum Kata {
 }
 
 impl Kata {
+    const ALL: [Kata; 3] = [Kata::Calculator, Kata::Dsa, Kata::Blank];
+
     fn as_str(&self) -> &'static str {
         match self {
             Kata::Calculator => "calculator",
             Kata::Dsa => "dsa",
-            Kata::Blank => "blank"
+            Kata::Blank => "blank",
         }
     }
 
@@ -65,292 +148,228 @@ impl Kata {
     }
 }
 
+/// How new day folders are named. Either way the days are told apart by their number.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum DayNaming {
+    /// `day1`, `day2`, ...
+    #[default]
+    Plain,
+    /// `day001`, `day002`, ..., which sort in order in file listings.
+    Padded,
+}
+
+impl DayNaming {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DayNaming::Plain => "plain",
+            DayNaming::Padded => "padded",
+        }
+    }
+
+    pub fn get(s: &str) -> Option<Self> {
+        match s {
+            "plain" => Some(DayNaming::Plain),
+            "padded" => Some(DayNaming::Padded),
+            _ => None,
+        }
+    }
+
+    fn folder_name(&self, number: u32) -> String {
+        match self {
+            DayNaming::Plain => format!("day{}", number),
+            DayNaming::Padded => format!("day{:03}", number),
+        }
+    }
+}
+
 pub struct KataInput {
     pub language: Language,
     pub kata: Kata,
 }
 
+/// How the template files of a day are set up, for the languages that have choices.
+#[derive(Default)]
+pub struct TemplateOptions {
+    /// The C# target framework, e.g. `net8.0`. Defaults to the newest installed SDK.
+    pub csharp_target_framework: Option<String>,
+    pub csharp_test_framework: CSharpTestFramework,
+    pub ts_runner: TsRunner,
+    pub rust_crate: RustCrateOptions,
+}
+
+impl TemplateOptions {
+    /// The options an existing day was generated with.
+    fn of_day(day_folder_path: &Path, language: Language) -> Self {
+        match language {
+            Language::CSharp => {
+                let (csharp_target_framework, csharp_test_framework) =
+                    read_csharp_project_settings(day_folder_path);
+                TemplateOptions {
+                    csharp_target_framework,
+                    csharp_test_framework,
+                    ..Default::default()
+                }
+            }
+            Language::TS => TemplateOptions {
+                ts_runner: TsRunner::of_day(day_folder_path),
+                ..Default::default()
+            },
+            Language::Rust => TemplateOptions {
+                rust_crate: RustCrateOptions::of_day(day_folder_path),
+                ..Default::default()
+            },
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Session<'a> {
     pub language: &'a str,
     pub kata: &'a str,
     pub day: PathBuf,
+    #[serde(default, borrow, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<&'a str>,
 }
 
-pub fn init() -> Result<(), String> {
-    check_and_install_tool("dotnet", "dotnet-sdk-6.0")?;
-    check_and_install_tool("npm", "npm")?;
-    check_and_install_tool("rust", "")?;
-
-    println!("Initialization completed successfully.");
-    Ok(())
-}
-
-pub fn generate_kata(input: KataInput) -> Result<(), String> {
-    let current_dir = std::env::current_dir().unwrap();
-    let language_dir = current_dir.join(input.language.as_str());
-    let kata_dir = language_dir.join(input.kata.as_str());
-
-    if !language_dir.exists() {
-        fs::create_dir(&language_dir).unwrap();
-    }
-    if !kata_dir.exists() {
-        fs::create_dir(&kata_dir).unwrap();
+impl<'a> Session<'a> {
+    fn read_json(root: &Path) -> Result<String, KataError> {
+        let path = root.join("session.json");
+        fs::read_to_string(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => KataError::SessionMissing { path },
+            _ => KataError::io("Error reading session file", e),
+        })
     }
 
-    let day_folder_path = find_next_day_folder(&kata_dir);
-    fs::create_dir(&day_folder_path).unwrap();
-
-    generate_source_files(&day_folder_path, input.language, input.kata)?;
-
-    if let Language::Rust = input.language {
-        link_rust_analyzer(&current_dir, &day_folder_path)?;
+    fn parse(root: &Path, session_json: &'a str) -> Result<Self, KataError> {
+        serde_json::from_str(session_json).map_err(|source| KataError::SessionCorrupt {
+            path: root.join("session.json"),
+            source,
+        })
     }
-
-    let session = Session {
-        language: input.language.as_str(),
-        kata: input.kata.as_str(),
-        day: day_folder_path,
-    };
-
-    let session_json = serde_json::to_string(&session)
-        .map_err(|err| format!("Error serializing session to JSON: {}", err))?;
-    file_utils::create_file_with_content(&current_dir, "session.json", &session_json)?;
-
-    Ok(())
 }
 
-pub fn run_tests(kata_input: KataInput) -> Result<(), String> {
-    let current_dir =
-        std::env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e))?;
-    let language_dir = current_dir.join(kata_input.language.as_str());
-    let kata_dir = language_dir.join(kata_input.kata.as_str());
-
-    let day_folder_path = find_most_recent_day_folder(&kata_dir).ok_or("No day folders found.")?;
-
-    match kata_input.language {
-        Language::Rust => run_rust_tests(&day_folder_path),
-        Language::TS => run_typescript_tests(&day_folder_path),
-        Language::CSharp => run_csharp_tests(&day_folder_path),
+fn run_day_tests(
+    day_folder_path: &Path,
+    language: Language,
+    limits: &RunLimits,
+) -> Result<TestReport, KataError> {
+    match language {
+        Language::Rust => run_rust_tests(day_folder_path, limits),
+        Language::TS => run_typescript_tests(day_folder_path, limits),
+        Language::CSharp => run_csharp_tests(day_folder_path, limits),
     }
 }
 
-pub fn clean() -> Result<(), String> {
-    let current_dir =
-        std::env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e))?;
+fn find_most_recent_day_folder(kata_dir: &Path) -> Option<PathBuf> {
+    list_day_folders(kata_dir).ok()?.pop()
+}
 
-    let session_path = current_dir.join("session.json");
-    if session_path.exists() {
-        std::fs::remove_file(&session_path)
-            .map_err(|e| format!("Failed to remove session.json: {}", e))?;
-    }
+/// The folder of day `number` of the kata, whichever naming scheme it was generated with.
+fn find_day_folder(kata_dir: &Path, number: u32) -> PathBuf {
+    list_day_folders(kata_dir)
+        .ok()
+        .and_then(|days| {
+            days.into_iter()
+                .find(|path| day_number(path) == Some(number))
+        })
+        .unwrap_or_else(|| kata_dir.join(DayNaming::Plain.folder_name(number)))
+}
 
-    let vscode_settings_path = current_dir.join(".vscode/settings.json");
-    if vscode_settings_path.exists() {
-        std::fs::remove_file(&vscode_settings_path)
-            .map_err(|e| format!("Failed to remove .vscode/settings.json: {}", e))?;
+fn day_number(day_folder_path: &Path) -> Option<u32> {
+    day_folder_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix("day"))
+        .and_then(|number| number.parse::<u32>().ok())
+}
+
+/// The day folders of a kata, sorted by day number.
+fn list_day_folders(kata_dir: &Path) -> Result<Vec<PathBuf>, KataError> {
+    if !kata_dir.exists() {
+        return Ok(Vec::new());
     }
 
-    let languages_dir = ["rust", "csharp", "typescript"];
-    for language in languages_dir {
-        let language_dir = current_dir.join(language);
-        if language_dir.exists() {
-            std::fs::remove_dir_all(&language_dir)
-                .map_err(|e| format!("Failed to remove the {} directory: {}", language, e))?;
+    let entries = fs::read_dir(kata_dir)
+        .map_err(|e| KataError::io(format!("Failed to read {}", kata_dir.display()), e))?;
+    let mut days = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|e| KataError::io("Failed to read directory entry", e))?
+            .path();
+        if let (Some(number), true) = (day_number(&path), path.is_dir()) {
+            days.push((number, path));
         }
     }
 
-    println!("Cleanup completed successfully.");
-    Ok(())
+    days.sort();
+    Ok(days.into_iter().map(|(_, path)| path).collect())
 }
 
-fn find_most_recent_day_folder(kata_dir: &PathBuf) -> Option<PathBuf> {
-    let day_folders: Vec<_> = fs::read_dir(kata_dir)
-        .ok()?
-        .filter_map(|entry| {
-            let entry = entry.ok()?;
-            let path = entry.path();
-            if path.is_dir()
-                && path
-                    .file_name()
-                    .and_then(|name| name.to_str())
-                    .map(|name| name.starts_with("day"))
-                    .unwrap_or(false)
-            {
-                Some(path)
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    if day_folders.is_empty() {
-        None
-    } else {
-        day_folders.into_iter().max()
-    }
+/// The folder of the day after the most recent one.
+fn find_next_day_folder(kata_dir: &Path, naming: DayNaming) -> PathBuf {
+    let next = find_most_recent_day_folder(kata_dir)
+        .and_then(|path| day_number(&path))
+        .map_or(1, |number| number + 1);
+    kata_dir.join(naming.folder_name(next))
 }
 
-fn find_next_day_folder(kata_dir: &Path) -> PathBuf {
-    let mut day_folder = 1;
-    let mut day_folder_path = kata_dir.join(&format!("day{}", day_folder));
-
-    while day_folder_path.exists() {
-        day_folder += 1;
-        day_folder_path = kata_dir.join(&format!("day{}", day_folder));
+fn install_dependencies(
+    root: &Path,
+    day_folder_path: &Path,
+    language: Language,
+    offline: bool,
+) -> Result<(), KataError> {
+    match language {
+        Language::TS => install_shared_typescript_dependencies(
+            &root.join(Language::TS.as_str()),
+            &[TsRunner::of_day(day_folder_path)],
+            offline,
+        ),
+        Language::Rust => use_vendored_registry(root, day_folder_path),
+        Language::CSharp => restore_csharp_project(root, day_folder_path, offline),
     }
-
-    day_folder_path
 }
 
-fn generate_source_files(
-    day_folder_path: &std::path::PathBuf,
+fn write_template_files(
+    day_folder_path: &Path,
     language: Language,
     kata: Kata,
-) -> Result<(), String> {
+    options: &TemplateOptions,
+) -> Result<(), KataError> {
     match language {
-        Language::TS => generate_typescript_files(day_folder_path, kata),
-        Language::Rust => generate_rust_files(day_folder_path, kata),
-        Language::CSharp => generate_csharp_files(day_folder_path, kata),
-    }
-}
-
-fn link_rust_analyzer(current_dir: &PathBuf, day_folder_path: &Path) -> Result<(), String> {
-    let vscode_dir = current_dir.join(".vscode");
-    if !vscode_dir.exists() {
-        fs::create_dir(&vscode_dir)
-            .map_err(|e| format!("Failed to create .vscode directory: {}", e))?;
-    }
-
-    let settings_path = vscode_dir.join("settings.json");
-    let mut settings_content = String::new();
-    let mut settings_json: Value = json!({
-        "rust-analyzer.linkedProjects": [
-        ]
-    });
-
-    if settings_path.exists() {
-        // Read existing settings file content
-        let mut file = File::open(&settings_path)
-            .map_err(|e| format!("Failed to open settings file: {}", e))?;
-        file.read_to_string(&mut settings_content)
-            .map_err(|e| format!("Failed to read settings file: {}", e))?;
-
-        // Parse existing content as JSON
-        settings_json = serde_json::from_str(&settings_content)
-            .map_err(|e| format!("Failed to parse settings file as JSON: {}", e))?;
+        Language::TS => write_typescript_template_files(day_folder_path, kata, options.ts_runner),
+        Language::Rust => generate_rust_files(day_folder_path, kata, &options.rust_crate),
+        Language::CSharp => write_csharp_template_files(
+            day_folder_path,
+            kata,
+            options.csharp_target_framework.as_deref(),
+            options.csharp_test_framework,
+        ),
     }
-
-    // Modify the array of linked projects
-    let linked_projects = settings_json
-        .get_mut("rust-analyzer.linkedProjects")
-        .and_then(|value| value.as_array_mut())
-        .ok_or("Invalid settings file format")?;
-
-    linked_projects.push(json!(format!(
-        "{}/Cargo.toml",
-        day_folder_path.strip_prefix(current_dir).unwrap().display()
-    )));
-
-    // Replace "<day_folder>" with actual day folder path
-    settings_content = serde_json::to_string_pretty(&settings_json)
-        .map_err(|e| format!("Failed to serialize settings JSON: {}", e))?;
-    settings_content = settings_content.replace("<day_folder>", &day_folder_path.to_string_lossy());
-
-    // Write the updated content back to the file
-    let mut file = File::create(&settings_path)
-        .map_err(|e| format!("Failed to create settings file: {}", e))?;
-    file.write_all(settings_content.as_bytes())
-        .map_err(|e| format!("Failed to write to settings file: {}", e))?;
-
-    Ok(())
 }
 
-fn check_and_install_tool(tool_name: &str, package_name: &str) -> Result<(), String> {
-    println!("Checking {}", tool_name);
-    match tool_name {
-        "rust" => check_and_install_rust(),
-        _ => {
-            let output = Command::new(tool_name)
-                .arg("--version")
-                .stdout(Stdio::inherit())
-                .stderr(Stdio::inherit())
-                .output();
-
-            if output.is_ok() {
-                println!("{} is already installed.", tool_name);
-                Ok(())
-            } else {
-                println!("Installing {}...", tool_name);
-                install_tool(package_name)?;
-                Ok(())
-            }
+/// Copies the source files of a day into another folder, except for the ones of the skipped role.
+/// Copying onto freshly generated templates skips the config files, which contain the day's name.
+fn copy_day_source_files(
+    from_day_path: &Path,
+    day_folder_path: &Path,
+    language: Language,
+    skipped_role: FileRole,
+) -> Result<(), KataError> {
+    for relative_path in file_utils::collect_source_files(from_day_path)? {
+        let path = relative_path.to_string_lossy().replace('\\', "/");
+        if language.file_role(&path) == skipped_role {
+            continue;
         }
-    }
-}
-
-fn check_and_install_rust() -> Result<(), String> {
-    let output = Command::new("rustc")
-        .arg("--version")
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .output()
-        .map_err(|e| format!("Failed to execute command: {}", e))?;
-
-    if output.status.success() {
-        println!("rust is already installed.");
-        Ok(())
-    } else {
-        println!("Installing rust...");
-        install_rust()?;
-        Ok(())
-    }
-}
 
-fn install_rust() -> Result<(), String> {
-    println!("Installing rust");
-    let download = Command::new("curl")
-        .arg("--proto")
-        .arg("https")
-        .arg("--tlsv1.2")
-        .arg("-sSf")
-        .arg("https://sh.rustup.rs")
-        .stdout(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to execute command: {}", e))?;
-
-    let output = Command::new("sh")
-        .stdin(Stdio::from(download.stdout.unwrap()))
-        .stderr(Stdio::inherit())
-        .output()
-        .map_err(|e| format!("Failed to execute command: {}", e))?;
-
-    if output.status.success() {
-        println!("rust installation successful.");
-        println!("Installing gcc...");
-        install_tool("gcc")?;
-        Ok(())
-    } else {
-        Err("Failed to install rust.".to_string())
+        let target_path = day_folder_path.join(&relative_path);
+        if let Some(parent) = target_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| KataError::io(format!("Failed to create {}", parent.display()), e))?;
+        }
+        fs::copy(from_day_path.join(&relative_path), &target_path)
+            .map_err(|e| KataError::io(format!("Failed to copy {}", relative_path.display()), e))?;
     }
-}
 
-fn install_tool(package_name: &str) -> Result<(), String> {
-    println!("Installing {}", package_name);
-    let output = Command::new("sudo")
-        .arg("apt-get")
-        .arg("install")
-        .arg("-y")
-        .arg(package_name)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .output()
-        .map_err(|e| format!("Failed to execute command: {}", e))?;
-
-    if output.status.success() {
-        println!("Installation successful.");
-        Ok(())
-    } else {
-        Err(format!("Failed to install the tool: {}", package_name))
-    }
+    Ok(())
 }
@@ -1,99 +1,734 @@
-use std::fs;
+use std::{error::Error, path::PathBuf, process::ExitCode, time::Duration};
 
-use clap::{Parser, Subcommand};
-use kata_machine::{Kata, KataInput, Language, Session};
+use clap::{Parser, Subcommand, ValueEnum};
+use kata_machine::{
+    is_valid_edition, is_valid_target_framework, is_valid_toolchain, parse_timeout,
+    CSharpTestFramework, CleanOptions, Config, DiffFormat, Editor, FuzzOptions, GenerateOptions,
+    InitOptions, Kata, KataError, KataInput, Language, PairMode, RunLimits, RustCrateOptions,
+    RustDevDependency, TemplateOptions, TsRunner, Workspace,
+};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Workspace folder, defaults to $KATA_MACHINE_HOME or the current directory
+    #[arg(long, global = true)]
+    root: Option<PathBuf>,
+    /// How errors are reported
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    /// Print every external command, where it ran, how long it took and its exit status
+    #[arg(short, long, global = true)]
+    verbose: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    Init,
+    Init {
+        /// Only set up these languages, e.g. rust,ts. Defaults to all of them
+        #[arg(short, long, value_delimiter = ',')]
+        language: Vec<String>,
+        /// Install without asking for confirmation, also passed on to the installers
+        #[arg(short, long)]
+        yes: bool,
+        /// Install into your home folder without sudo: rustup, dotnet-install.sh and a Node tarball in ~/.local
+        #[arg(long)]
+        user: bool,
+        /// Print what would be installed, and how, without installing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Generate the next day of a kata. Options left out fall back to the config, see `config list`
     Generate {
-        #[arg(short, long, default_value_t = String::from("rust"))]
-        language: String,
-        #[arg(short, long, default_value_t = String::from("calculator"))]
-        kata: String,
+        /// Defaults to the `language` of the config, then rust
+        #[arg(short, long)]
+        language: Option<String>,
+        /// Defaults to the `kata` of the config, then calculator
+        #[arg(short, long)]
+        kata: Option<String>,
+        #[arg(long)]
+        from_day: Option<u32>,
+        /// Editor to configure: vscode, helix, neovim or jetbrains. Defaults to the config's, then the session's, then vscode
+        #[arg(short, long)]
+        editor: Option<String>,
+        /// Make rust/Cargo.toml a workspace of all Rust days sharing one target folder
+        #[arg(long)]
+        cargo_workspace: bool,
+        /// Install dependencies from the local caches only, without the network
+        #[arg(long)]
+        offline: bool,
+        /// C# target framework, e.g. net8.0. Defaults to the newest installed SDK
+        #[arg(long)]
+        target_framework: Option<String>,
+        /// C# test framework: nunit, xunit or mstest. Defaults to nunit
+        #[arg(long)]
+        test_framework: Option<String>,
+        /// TypeScript test runner: jest, vitest, node, deno or bun. Defaults to jest
+        #[arg(long)]
+        ts_runner: Option<String>,
+        /// Rust toolchain to pin in rust-toolchain.toml: stable, beta, nightly or a version like 1.82.0
+        #[arg(long)]
+        toolchain: Option<String>,
+        /// Rust edition: 2015, 2018, 2021 or 2024. Defaults to 2021
+        #[arg(long)]
+        edition: Option<String>,
+        /// Rust dev-dependency from the vendored registry: proptest, rstest or pretty_assertions. Repeatable
+        #[arg(long = "dev-dependency")]
+        dev_dependencies: Vec<String>,
+        /// Make the Rust library no_std outside of its tests
+        #[arg(long)]
+        no_std: bool,
+    },
+    Test {
+        /// Kill the tests after this long, e.g. 30s or 5m. Defaults to the config's test.timeout, 10m
+        #[arg(long)]
+        timeout: Option<String>,
+        /// Kill the tests once they use more than this many megabytes. Defaults to the config's test.memory_limit
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        memory_limit: Option<u64>,
+    },
+    /// Score the most recent day against the hidden acceptance tests of its kata
+    Submit {
+        /// Show the reference solution afterwards
+        #[arg(long)]
+        reveal: bool,
+    },
+    /// Compare the most recent day with the reference of its kata on random inputs
+    Fuzz {
+        /// The number of random inputs
+        #[arg(long, default_value_t = 500, value_parser = clap::value_parser!(u64).range(1..))]
+        runs: u64,
+        /// Seed the input generator to repeat a run
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Add the counter-example as a test case to the day's test file
+        #[arg(long)]
+        append: bool,
+    },
+    Reset {
+        #[arg(short, long)]
+        yes: bool,
+    },
+    Replay {
+        #[arg(short, long)]
+        day: u32,
+        #[arg(short, long)]
+        language: Option<String>,
+        #[arg(short, long)]
+        kata: Option<String>,
+    },
+    Review {
+        #[arg(short, long)]
+        day: Option<u32>,
+        #[arg(short, long)]
+        language: Option<String>,
+        #[arg(short, long)]
+        kata: Option<String>,
+    },
+    Diff {
+        #[arg(short, long)]
+        kata: Option<String>,
+        #[arg(short, long)]
+        day: Vec<u32>,
+        #[arg(short, long)]
+        language: Vec<String>,
+        #[arg(long, conflicts_with = "side_by_side")]
+        stat: bool,
+        #[arg(short, long)]
+        side_by_side: bool,
+    },
+    Pair {
+        #[arg(short, long, value_delimiter = ',', required = true)]
+        with: Vec<String>,
+        #[arg(short, long)]
+        mob: bool,
+        #[arg(
+            short,
+            long,
+            default_value_t = 5,
+            requires = "mob",
+            value_parser = clap::value_parser!(u64).range(1..)
+        )]
+        rotate: u64,
+    },
+    Editor {
+        #[command(subcommand)]
+        command: EditorCommands,
+    },
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Check the tools, the session, the editor settings and the day folders without changing anything
+    Doctor,
+    Clean {
+        #[arg(short, long)]
+        language: Option<String>,
+        #[arg(short, long)]
+        kata: Option<String>,
+        /// Only remove days untouched for this long, e.g. 30d, 12h or 2w
+        #[arg(long, value_parser = parse_age)]
+        older_than: Option<Duration>,
+        /// Keep the most recent days of every kata
+        #[arg(long)]
+        keep_last: Option<usize>,
+        /// List what would be removed without removing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Move everything to .kata/trash instead of deleting it
+        #[arg(long)]
+        trash: bool,
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum EditorCommands {
+    /// Reconcile the linked projects in the editor config with the existing day folders
+    Sync {
+        /// Also configure this editor: vscode, helix, neovim or jetbrains
+        #[arg(short, long)]
+        editor: Option<String>,
+        #[arg(long)]
+        dry_run: bool,
     },
-    Test,
-    Clean,
 }
 
-fn main() -> Result<(), String> {
-    let cli = Cli::parse();
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the value of a key, e.g. rust.edition
+    Get { key: String },
+    /// Set a key in the workspace kata_machine.toml. Lists are comma separated
+    Set {
+        key: String,
+        value: String,
+        /// Write to the user config in ~/.config/kata_machine/config.toml instead
+        #[arg(long)]
+        user: bool,
+    },
+    /// Print every key with its value and where the value comes from
+    List,
+}
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(error) if error.use_stderr() && json_format_requested() => {
+            // clap's own error message, without its "error: " prefix and usage hints
+            let rendered = error.to_string();
+            let message = rendered
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim_start_matches("error: ");
+            let error = KataError::InvalidInput(message.to_string());
+            report_error(&error, OutputFormat::Json);
+            return ExitCode::from(error.exit_code() as u8);
+        }
+        Err(error) => error.exit(),
+    };
+    kata_machine::set_verbose(cli.verbose);
+    kata_machine::kill_supervised_groups_on_signals();
+
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            report_error(&error, cli.format);
+            ExitCode::from(error.exit_code() as u8)
+        }
+    }
+}
+
+/// Whether `--format json` is on the command line, for errors raised before it is parsed.
+fn json_format_requested() -> bool {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter().any(|arg| arg == "--format=json")
+        || args
+            .windows(2)
+            .any(|pair| pair[0] == "--format" && pair[1] == "json")
+}
+
+fn report_error(error: &KataError, format: OutputFormat) {
+    match format {
+        OutputFormat::Text => {
+            eprintln!("Error: {}", error);
+            let mut source = error.source();
+            while let Some(cause) = source {
+                eprintln!("  Caused by: {}", cause);
+                source = cause.source();
+            }
+        }
+        OutputFormat::Json => eprintln!("{}", error.to_json()),
+    }
+}
+
+fn run(cli: &Cli) -> Result<(), KataError> {
+    let workspace = || Workspace::locate(cli.root.clone());
 
     match &cli.command {
-        Some(Commands::Init) => kata_machine::init(),
-        Some(Commands::Generate { language, kata }) => {
-            let (l, k) = validate_generate_input(language, kata)?;
-            kata_machine::generate_kata(KataInput {
-                language: l,
-                kata: k,
-            })
+        Some(Commands::Init {
+            language,
+            yes,
+            user,
+            dry_run,
+        }) => workspace()?.init(&InitOptions {
+            languages: language
+                .iter()
+                .map(|language| validate_language(language))
+                .collect::<Result<_, _>>()?,
+            user: *user,
+            dry_run: *dry_run,
+            skip_confirmation: *yes,
+        }),
+        Some(Commands::Generate {
+            language,
+            kata,
+            from_day,
+            editor,
+            cargo_workspace,
+            offline,
+            target_framework,
+            test_framework,
+            ts_runner,
+            toolchain,
+            edition,
+            dev_dependencies,
+            no_std,
+        }) => {
+            let workspace = workspace()?;
+            let config = workspace.config()?;
+            let language = language.as_deref().or(config.text("language"));
+            let kata = kata.as_deref().or(config.text("kata"));
+            let (l, k) = validate_generate_input(
+                language.unwrap_or("rust"),
+                kata.unwrap_or("calculator"),
+            )?;
+            if l != Language::CSharp && (target_framework.is_some() || test_framework.is_some()) {
+                return Err(KataError::InvalidInput(
+                    "--target-framework and --test-framework only apply to C# days.".to_string(),
+                ));
+            }
+            if l != Language::TS && ts_runner.is_some() {
+                return Err(KataError::InvalidInput(
+                    "--ts-runner only applies to TypeScript days.".to_string(),
+                ));
+            }
+            let has_rust_options =
+                toolchain.is_some() || edition.is_some() || !dev_dependencies.is_empty() || *no_std;
+            if l != Language::Rust && has_rust_options {
+                return Err(KataError::InvalidInput(
+                    "--toolchain, --edition, --dev-dependency and --no-std only apply to Rust days."
+                        .to_string(),
+                ));
+            }
+            // The config holds the defaults of every language, only those of the generated one
+            // are read and validated. The flags are checked above
+            config.check_language(l)?;
+            let or_config = |flag: &Option<String>, key: &str, language: Language| {
+                flag.as_deref()
+                    .or_else(|| config.text(key).filter(|_| l == language))
+                    .map(str::to_string)
+            };
+            let dev_dependencies = if dev_dependencies.is_empty() && l == Language::Rust {
+                config
+                    .list("rust.dev_dependencies")
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect()
+            } else {
+                dev_dependencies.clone()
+            };
+            let options = GenerateOptions {
+                from_day: *from_day,
+                editor: editor.as_deref().map(validate_editor).transpose()?,
+                cargo_workspace: *cargo_workspace || config.flag("rust.cargo_workspace"),
+                offline: *offline,
+                template: TemplateOptions {
+                    csharp_target_framework: or_config(target_framework, "csharp.target_framework", Language::CSharp)
+                        .as_deref()
+                        .map(validate_target_framework)
+                        .transpose()?,
+                    csharp_test_framework: or_config(test_framework, "csharp.test_framework", Language::CSharp)
+                        .as_deref()
+                        .map(validate_test_framework)
+                        .transpose()?
+                        .unwrap_or_default(),
+                    ts_runner: or_config(ts_runner, "typescript.runner", Language::TS)
+                        .as_deref()
+                        .map(validate_ts_runner)
+                        .transpose()?
+                        .unwrap_or_default(),
+                    rust_crate: RustCrateOptions {
+                        toolchain: or_config(toolchain, "rust.toolchain", Language::Rust)
+                            .as_deref()
+                            .map(validate_toolchain)
+                            .transpose()?,
+                        edition: or_config(edition, "rust.edition", Language::Rust)
+                            .as_deref()
+                            .map(validate_edition)
+                            .transpose()?,
+                        dev_dependencies: dev_dependencies
+                            .iter()
+                            .map(|dependency| validate_dev_dependency(dependency))
+                            .collect::<Result<_, _>>()?,
+                        no_std: *no_std || config.flag("rust.no_std"),
+                    },
+                },
+            };
+            workspace.generate_kata(
+                KataInput {
+                    language: l,
+                    kata: k,
+                },
+                &options,
+            )
+        }
+        Some(Commands::Test {
+            timeout,
+            memory_limit,
+        }) => {
+            let workspace = workspace()?;
+            let limits = RunLimits {
+                timeout: timeout.as_deref().map(validate_timeout).transpose()?,
+                memory_limit_mb: *memory_limit,
+            };
+            workspace.run_tests(workspace.session_input()?, limits)
+        }
+        Some(Commands::Submit { reveal }) => {
+            let workspace = workspace()?;
+            workspace.submit(workspace.session_input()?, *reveal)
+        }
+        Some(Commands::Fuzz { runs, seed, append }) => {
+            let workspace = workspace()?;
+            let options = FuzzOptions {
+                runs: *runs as usize,
+                seed: *seed,
+                append: *append,
+            };
+            workspace.fuzz(workspace.session_input()?, &options)
+        }
+        Some(Commands::Reset { yes }) => {
+            let workspace = workspace()?;
+            workspace.reset(workspace.session_input()?, *yes)
         }
-        Some(Commands::Test) => {
-            let (l, k) = get_test_input_from_session()?;
-            kata_machine::run_tests(KataInput {
-                language: l,
-                kata: k,
-            })
+        Some(Commands::Replay {
+            day,
+            language,
+            kata,
+        }) => {
+            let workspace = workspace()?;
+            let (l, k) = get_input_with_session_defaults(&workspace, language, kata)?;
+            workspace.replay(
+                KataInput {
+                    language: l,
+                    kata: k,
+                },
+                *day,
+            )
+        }
+        Some(Commands::Review {
+            day,
+            language,
+            kata,
+        }) => {
+            let workspace = workspace()?;
+            let (l, k) = get_input_with_session_defaults(&workspace, language, kata)?;
+            workspace.review(
+                KataInput {
+                    language: l,
+                    kata: k,
+                },
+                *day,
+            )
+        }
+        Some(Commands::Diff {
+            kata,
+            day,
+            language,
+            stat,
+            side_by_side,
+        }) => {
+            let workspace = workspace()?;
+            let (left, right, k) = validate_diff_input(&workspace, kata, day, language)?;
+            let format = if *stat {
+                DiffFormat::Stat
+            } else if *side_by_side {
+                DiffFormat::SideBySide
+            } else {
+                DiffFormat::Unified
+            };
+            workspace.diff(k, left, right, format)
+        }
+        Some(Commands::Pair { with, mob, rotate }) => {
+            let workspace = workspace()?;
+            let mode = if *mob {
+                PairMode::Mob {
+                    rotation: Duration::from_secs(rotate.saturating_mul(60)),
+                }
+            } else {
+                PairMode::PingPong
+            };
+            workspace.pair(workspace.session_input()?, with.clone(), mode)
+        }
+        Some(Commands::Editor {
+            command: EditorCommands::Sync { editor, dry_run },
+        }) => {
+            let editor = editor.as_deref().map(validate_editor).transpose()?;
+            workspace()?.editor_sync(editor, *dry_run)
+        }
+        Some(Commands::Config { command }) => {
+            let workspace = workspace()?;
+            match command {
+                ConfigCommands::Get { key } => {
+                    let config = workspace.config()?;
+                    match config.get(key) {
+                        Some((value, _)) => println!("{}", value),
+                        None if Config::is_key(key) => {}
+                        None => {
+                            return Err(KataError::InvalidInput(format!(
+                                "Unknown config key: {}. Run `kata_machine config list` to see the keys.",
+                                key
+                            )))
+                        }
+                    }
+                    Ok(())
+                }
+                ConfigCommands::Set { key, value, user } => {
+                    let path = if *user {
+                        kata_machine::user_config_path().ok_or_else(|| {
+                            KataError::InvalidInput(
+                                "Neither $XDG_CONFIG_HOME nor $HOME is set.".to_string(),
+                            )
+                        })?
+                    } else {
+                        kata_machine::workspace_config_path(workspace.root())
+                    };
+                    kata_machine::set_value(&path, key, value)?;
+                    println!("Set {} = {} in {}", key, value, path.display());
+                    Ok(())
+                }
+                ConfigCommands::List => {
+                    let config = workspace.config()?;
+                    for (key, value) in config.entries() {
+                        match value {
+                            Some((value, layer)) => {
+                                println!("{} = {} ({})", key, value, layer.as_str())
+                            }
+                            None => println!("{} is not set", key),
+                        }
+                    }
+                    Ok(())
+                }
+            }
         }
-        Some(Commands::Clean) => kata_machine::clean(),
-        None => Err(String::from(
-            "Invalid command. Available commands: init, generate, test, clean",
-        )),
+        Some(Commands::Doctor) => workspace()?.doctor(),
+        Some(Commands::Clean {
+            language,
+            kata,
+            older_than,
+            keep_last,
+            dry_run,
+            trash,
+            yes,
+        }) => {
+            let options = CleanOptions {
+                language: language.as_deref().map(validate_language).transpose()?,
+                kata: kata.as_deref().map(validate_kata).transpose()?,
+                older_than: *older_than,
+                keep_last: *keep_last,
+                dry_run: *dry_run,
+                trash: *trash,
+                skip_confirmation: *yes,
+            };
+            workspace()?.clean(&options)
+        }
+        None => Err(KataError::InvalidInput(String::from(
+            "Invalid command. Available commands: init, generate, test, submit, fuzz, reset, replay, review, diff, pair, editor, config, doctor, clean",
+        ))),
     }
 }
 
-fn validate_generate_input(language: &str, kata: &str) -> Result<(Language, Kata), String> {
+fn validate_generate_input(language: &str, kata: &str) -> Result<(Language, Kata), KataError> {
     let l = validate_language(language)?;
     let k = validate_kata(kata)?;
 
     Ok((l, k))
 }
 
-fn get_test_input_from_session() -> Result<(Language, Kata), String> {
-    let current_dir = std::env::current_dir()
-        .map_err(|err| format!("Error getting current directory: {}", err))?;
+type DiffSide = (Language, Option<u32>);
+
+fn validate_diff_input(
+    workspace: &Workspace,
+    kata: &Option<String>,
+    days: &[u32],
+    languages: &[String],
+) -> Result<(DiffSide, DiffSide, Kata), KataError> {
+    if days.len() > 2 || languages.len() > 2 {
+        return Err(KataError::InvalidInput(String::from(
+            "Diff compares two attempts: pass at most two days and two languages.",
+        )));
+    }
+
+    let (left_language, k) =
+        get_input_with_session_defaults(workspace, &languages.first().cloned(), kata)?;
+    let right_language = match languages.get(1) {
+        Some(language) => validate_language(language)?,
+        None => left_language,
+    };
+    let (left_day, right_day) = match days {
+        [] => (None, None),
+        [day] => (Some(*day), Some(*day)),
+        [left_day, right_day, ..] => (Some(*left_day), Some(*right_day)),
+    };
 
-    let session_file_path = current_dir.join("session.json");
+    if left_language == right_language && left_day == right_day {
+        return Err(KataError::InvalidInput(String::from(
+            "Nothing to compare. Pass two days (--day 2 --day 5) or two languages (--language rust --language ts).",
+        )));
+    }
 
-    let session_json = fs::read_to_string(session_file_path)
-        .map_err(|err| format!("Error reading session file: {}", err))?;
+    Ok(((left_language, left_day), (right_language, right_day), k))
+}
 
-    let session: Session = serde_json::from_str(&session_json)
-        .map_err(|err| format!("Error parsing session JSON: {}", err))?;
+fn get_input_with_session_defaults(
+    workspace: &Workspace,
+    language: &Option<String>,
+    kata: &Option<String>,
+) -> Result<(Language, Kata), KataError> {
+    if let (Some(language), Some(kata)) = (language, kata) {
+        return validate_generate_input(language, kata);
+    }
 
-    let l = Language::get(session.language).unwrap();
-    let k = Kata::get(session.kata).unwrap();
+    let session = workspace.session_input()?;
+    let l = match language {
+        Some(language) => validate_language(language)?,
+        None => session.language,
+    };
+    let k = match kata {
+        Some(kata) => validate_kata(kata)?,
+        None => session.kata,
+    };
 
     Ok((l, k))
 }
 
-fn validate_language(language: &str) -> Result<Language, String> {
+fn validate_language(language: &str) -> Result<Language, KataError> {
     let valid_languages = ["rust", "ts", "cs"];
     if !valid_languages.contains(&language) {
-        return Err(format!(
+        return Err(KataError::InvalidInput(format!(
             "Invalid language: {}. Supported languages are: {}",
             language,
             valid_languages.join(", ")
-        ));
+        )));
     }
     Ok(Language::get(language).unwrap())
 }
 
-fn validate_kata(kata: &str) -> Result<Kata, String> {
+fn validate_kata(kata: &str) -> Result<Kata, KataError> {
     let valid_katas = ["calculator", "dsa", "blank"];
     if !valid_katas.contains(&kata) {
-        return Err(format!(
+        return Err(KataError::InvalidInput(format!(
             "Invalid kata: {}. Supported katas are: {}",
             kata,
             valid_katas.join(", ")
-        ));
+        )));
     }
     Ok(Kata::get(kata).unwrap())
 }
+
+/// Parses an age such as `30d`, `12h`, `45m` or `2w`. A plain number is in days.
+fn parse_age(age: &str) -> Result<Duration, String> {
+    let with_unit = match age.chars().all(|c| c.is_ascii_digit()) {
+        true => format!("{}d", age),
+        false => age.to_string(),
+    };
+    parse_timeout(&with_unit)
+        .ok_or_else(|| format!("Invalid age: {}. Use e.g. 30d, 12h, 45m or 2w", age))
+}
+
+fn validate_timeout(timeout: &str) -> Result<Duration, KataError> {
+    parse_timeout(timeout).ok_or_else(|| {
+        KataError::InvalidInput(format!(
+            "Invalid timeout: {}. Use e.g. 90s, 5m or 1h",
+            timeout
+        ))
+    })
+}
+
+fn validate_target_framework(target_framework: &str) -> Result<String, KataError> {
+    if is_valid_target_framework(target_framework) {
+        Ok(target_framework.to_string())
+    } else {
+        Err(KataError::InvalidInput(format!(
+            "Invalid target framework: {}. Use the form net8.0",
+            target_framework
+        )))
+    }
+}
+
+fn validate_test_framework(test_framework: &str) -> Result<CSharpTestFramework, KataError> {
+    CSharpTestFramework::get(test_framework).ok_or_else(|| {
+        KataError::InvalidInput(format!(
+            "Invalid test framework: {}. Supported test frameworks are: nunit, xunit, mstest",
+            test_framework
+        ))
+    })
+}
+
+fn validate_ts_runner(ts_runner: &str) -> Result<TsRunner, KataError> {
+    TsRunner::get(ts_runner).ok_or_else(|| {
+        KataError::InvalidInput(format!(
+            "Invalid TypeScript runner: {}. Supported runners are: jest, vitest, node, deno, bun",
+            ts_runner
+        ))
+    })
+}
+
+fn validate_toolchain(toolchain: &str) -> Result<String, KataError> {
+    if is_valid_toolchain(toolchain) {
+        Ok(toolchain.to_string())
+    } else {
+        Err(KataError::InvalidInput(format!(
+            "Invalid toolchain: {}. Use stable, beta, nightly or a version like 1.82.0",
+            toolchain
+        )))
+    }
+}
+
+fn validate_edition(edition: &str) -> Result<String, KataError> {
+    if is_valid_edition(edition) {
+        Ok(edition.to_string())
+    } else {
+        Err(KataError::InvalidInput(format!(
+            "Invalid edition: {}. Supported editions are: 2015, 2018, 2021, 2024",
+            edition
+        )))
+    }
+}
+
+fn validate_dev_dependency(dependency: &str) -> Result<RustDevDependency, KataError> {
+    RustDevDependency::get(dependency).ok_or_else(|| {
+        KataError::InvalidInput(format!(
+            "Invalid dev-dependency: {}. Supported dev-dependencies are: proptest, rstest, pretty_assertions",
+            dependency
+        ))
+    })
+}
+
+fn validate_editor(editor: &str) -> Result<Editor, KataError> {
+    Editor::get(editor).ok_or_else(|| {
+        KataError::InvalidInput(format!(
+            "Invalid editor: {}. Supported editors are: vscode, helix, neovim, jetbrains",
+            editor
+        ))
+    })
+}
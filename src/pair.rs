@@ -0,0 +1,218 @@
+use crate::{
+    console_utils::prompt, errors::KataError, languages::test_report::TestReport,
+    time_utils::unix_timestamp,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::Path,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+pub enum PairMode {
+    /// One participant writes a failing test, the next one makes it pass and writes the next test.
+    PingPong,
+    /// The driver rotates to the next participant whenever the rotation timer runs out.
+    Mob { rotation: Duration },
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PairPhase {
+    Red,
+    Green,
+    Mob,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PairTurn {
+    pub driver: String,
+    pub phase: PairPhase,
+    pub started_at: u64,
+    pub duration_secs: u64,
+    pub test_runs: u32,
+    pub passed: bool,
+}
+
+/// Runs the session, logging every turn to `pairing_path`. `run_tests` runs the tests of the
+/// day the way `test` does, so the runs are recorded like any other.
+pub fn run_pair_session(
+    pairing_path: &Path,
+    participants: Vec<String>,
+    mode: PairMode,
+    run_tests: &mut dyn FnMut() -> Result<TestReport, KataError>,
+) -> Result<(), KataError> {
+    if participants.len() < 2 {
+        return Err(KataError::InvalidInput(
+            "Pairing needs at least two participants.".to_string(),
+        ));
+    }
+
+    match mode {
+        PairMode::PingPong => run_ping_pong(pairing_path, &participants, run_tests),
+        PairMode::Mob { rotation } => run_mob(pairing_path, &participants, rotation, run_tests),
+    }
+}
+
+fn run_ping_pong(
+    pairing_path: &Path,
+    participants: &[String],
+    run_tests: &mut dyn FnMut() -> Result<TestReport, KataError>,
+) -> Result<(), KataError> {
+    let mut phase_index: usize = 0;
+
+    loop {
+        // Whoever made the tests green also writes the next failing test.
+        let driver = &participants[phase_index.div_ceil(2) % participants.len()];
+        let phase = if phase_index.is_multiple_of(2) {
+            PairPhase::Red
+        } else {
+            PairPhase::Green
+        };
+        let started_at = unix_timestamp();
+        let start = Instant::now();
+        let mut test_runs = 0;
+
+        loop {
+            let instruction = match phase {
+                PairPhase::Red => "write a failing test",
+                _ => "make the tests pass",
+            };
+            if !prompt(&format!(
+                "{}: {}, then press Enter to run the tests (q to quit)",
+                driver, instruction
+            ))? {
+                println!("Pair session ended.");
+                return Ok(());
+            }
+
+            let passed = run_tests()?.success;
+            test_runs += 1;
+
+            match (phase, passed) {
+                (PairPhase::Red, true) => {
+                    println!("The tests are still green. Write a failing test before handing over.")
+                }
+                (PairPhase::Green, false) => println!("The tests are still red. Keep going."),
+                _ => break,
+            }
+        }
+
+        let passed = matches!(phase, PairPhase::Green);
+        log_turn(
+            pairing_path,
+            PairTurn {
+                driver: driver.clone(),
+                phase,
+                started_at,
+                duration_secs: start.elapsed().as_secs(),
+                test_runs,
+                passed,
+            },
+        )?;
+
+        let next_driver = &participants[((phase_index + 2) / 2) % participants.len()];
+        if passed {
+            println!("Green! {} now writes the next failing test.", next_driver);
+        } else {
+            println!("Red confirmed. Over to {} to make it pass.", next_driver);
+        }
+
+        phase_index += 1;
+    }
+}
+
+fn run_mob(
+    pairing_path: &Path,
+    participants: &[String],
+    rotation: Duration,
+    run_tests: &mut dyn FnMut() -> Result<TestReport, KataError>,
+) -> Result<(), KataError> {
+    let mut turn = 0;
+
+    loop {
+        let driver = &participants[turn % participants.len()];
+        let next_driver = &participants[(turn + 1) % participants.len()];
+        let started_at = unix_timestamp();
+        let start = Instant::now();
+
+        println!(
+            "{} is driving for the next {} minute(s).",
+            driver,
+            rotation.as_secs() / 60
+        );
+        let (stop_timer, timer) = start_rotation_timer(rotation, next_driver.clone());
+
+        let keep_going = prompt(&format!(
+            "{}: press Enter to run the tests and hand over (q to quit)",
+            driver
+        ));
+        // The receiver may already be gone if the timer ran out, so ignore send errors.
+        let _ = stop_timer.send(());
+        let _ = timer.join();
+
+        if !keep_going? {
+            println!("Mob session ended.");
+            return Ok(());
+        }
+
+        let passed = run_tests()?.success;
+        log_turn(
+            pairing_path,
+            PairTurn {
+                driver: driver.clone(),
+                phase: PairPhase::Mob,
+                started_at,
+                duration_secs: start.elapsed().as_secs(),
+                test_runs: 1,
+                passed,
+            },
+        )?;
+
+        println!(
+            "Tests are {}. Rotating to {}.",
+            if passed { "green" } else { "red" },
+            next_driver
+        );
+        turn += 1;
+    }
+}
+
+fn start_rotation_timer(
+    rotation: Duration,
+    next_driver: String,
+) -> (mpsc::Sender<()>, thread::JoinHandle<()>) {
+    let (sender, receiver) = mpsc::channel();
+
+    let handle = thread::spawn(move || {
+        if let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(rotation) {
+            println!(
+                "\x07Time is up! Finish your thought and press Enter to hand over to {}.",
+                next_driver
+            );
+        }
+    });
+
+    (sender, handle)
+}
+
+fn log_turn(pairing_path: &Path, turn: PairTurn) -> Result<(), KataError> {
+    let mut turns: Vec<PairTurn> = match fs::read_to_string(pairing_path) {
+        Ok(pairing_json) => serde_json::from_str(&pairing_json)
+            .map_err(|e| KataError::json("Error parsing the pairing log", e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(KataError::io("Error reading the pairing log", e)),
+    };
+    turns.push(turn);
+
+    if let Some(parent) = pairing_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| KataError::io("Failed to create history directory", e))?;
+    }
+    let pairing_json = serde_json::to_string(&turns)
+        .map_err(|e| KataError::json("Error serializing the pairing log to JSON", e))?;
+    fs::write(pairing_path, pairing_json)
+        .map_err(|e| KataError::io(format!("Failed to write {}", pairing_path.display()), e))
+}
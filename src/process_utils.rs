@@ -0,0 +1,496 @@
+use crate::errors::KataError;
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    path::PathBuf,
+    process::{Command, ExitStatus, Stdio},
+    sync::atomic::{AtomicBool, AtomicI32, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Echo every external command and its outcome to stderr.
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+/// What was run, where, for how long and how it ended.
+pub struct CommandRecord {
+    pub command_line: String,
+    pub working_dir: Option<PathBuf>,
+    pub duration: Duration,
+    pub status: ExitStatus,
+}
+
+impl fmt::Display for CommandRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}`", self.command_line)?;
+        if let Some(working_dir) = &self.working_dir {
+            write!(f, " in {}", working_dir.display())?;
+        }
+        write!(
+            f,
+            " exited with {} after {:.1}s",
+            self.status,
+            self.duration.as_secs_f64()
+        )
+    }
+}
+
+/// Runs the command with inherited stdio and fails if it does not exit successfully.
+pub fn run(command: &mut Command) -> Result<CommandRecord, KataError> {
+    let record = run_unchecked(command)?;
+    if record.status.success() {
+        Ok(record)
+    } else {
+        Err(KataError::CommandFailed {
+            command_line: record.command_line,
+            working_dir: record.working_dir,
+            status: record.status.code(),
+            duration: record.duration,
+        })
+    }
+}
+
+/// Runs the command with inherited stdio, leaving the exit status to the caller.
+pub fn run_unchecked(command: &mut Command) -> Result<CommandRecord, KataError> {
+    let (command_line, working_dir) = describe(command);
+    echo_start(&command_line, &working_dir);
+
+    let start = Instant::now();
+    let status = command
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .map_err(|e| KataError::spawn(&program_name(command), e))?;
+
+    Ok(finish(command_line, working_dir, start, status))
+}
+
+/// Runs the command with its stdout captured and its stderr discarded, for probing tools
+/// without their banners reaching the terminal. The exit status is left to the caller.
+pub fn run_captured(command: &mut Command) -> Result<(CommandRecord, String), KataError> {
+    let (command_line, working_dir) = describe(command);
+    echo_start(&command_line, &working_dir);
+
+    let start = Instant::now();
+    let output = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| KataError::spawn(&program_name(command), e))?;
+
+    Ok((
+        finish(command_line, working_dir, start, output.status),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    ))
+}
+
+/// Bounds for a supervised command, covering the command and every process it starts.
+#[derive(Clone, Copy, Default)]
+pub struct RunLimits {
+    /// Wall-clock time, after which the whole process tree is killed.
+    pub timeout: Option<Duration>,
+    /// In megabytes, for the processes of the tree together. On Linux the kernel enforces it
+    /// through a cgroup v2 `memory.max` when the memory controller is delegated to kata_machine,
+    /// and otherwise the tree is killed once its resident memory goes over it, which is polled.
+    /// Other platforms cannot enforce it. The compiler and runtime count too.
+    pub memory_limit_mb: Option<u64>,
+}
+
+/// Why the supervisor stopped a command.
+#[derive(Clone, Copy, PartialEq)]
+pub enum LimitExceeded {
+    Timeout(Duration),
+    Memory(u64),
+}
+
+/// How often the supervisor checks the clock and the memory of the process tree.
+const SUPERVISOR_POLL: Duration = Duration::from_millis(50);
+
+/// Runs the command while echoing its stdout and stderr, and returns the record together with
+/// everything the command printed. The exit status is left to the caller.
+/// The command runs in its own process group under the limits.
+/// When a limit is hit the whole group is killed, and so are processes left behind by a
+/// command that exited normally, so a runaway grandchild never outlives the run.
+pub fn run_supervised(
+    command: &mut Command,
+    limits: &RunLimits,
+) -> Result<(CommandRecord, String, Option<LimitExceeded>), KataError> {
+    let (command_line, working_dir) = describe(command);
+    echo_start(&command_line, &working_dir);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        command.process_group(0);
+    }
+    let memory_cgroup = limits.memory_limit_mb.and_then(MemoryCgroup::create);
+    if limits.memory_limit_mb.is_some() && !cfg!(target_os = "linux") {
+        eprintln!("Warning: the memory limit is only enforced on Linux, it is ignored here.");
+    }
+
+    let start = Instant::now();
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| KataError::spawn(&program_name(command), e))?;
+
+    let group = SupervisedGroup::new(child.id());
+    // The child has exec'd by now, but has had no time to start the compiler or the tests,
+    // which inherit the cgroup
+    let cgroup = memory_cgroup.filter(|cgroup| cgroup.add(child.id()));
+
+    let stdout = child.stdout.take().unwrap();
+    let stderr = child.stderr.take().unwrap();
+    let stdout_reader = thread::spawn(move || tee(stdout, io::stdout()));
+    let stderr_reader = thread::spawn(move || tee(stderr, io::stderr()));
+
+    let wait_error = |e| KataError::io(format!("Failed to wait for `{}`", command_line), e);
+    let mut exceeded = None;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(wait_error)? {
+            break status;
+        }
+
+        exceeded = match (limits.timeout, limits.memory_limit_mb) {
+            (Some(timeout), _) if start.elapsed() >= timeout => {
+                Some(LimitExceeded::Timeout(timeout))
+            }
+            (_, Some(limit_mb))
+                if process_group_memory(child.id())
+                    .is_some_and(|bytes| bytes > limit_mb.saturating_mul(1024 * 1024)) =>
+            {
+                Some(LimitExceeded::Memory(limit_mb))
+            }
+            _ => None,
+        };
+        if exceeded.is_some() {
+            kill_process_group(&mut child);
+            break child.wait().map_err(wait_error)?;
+        }
+        thread::sleep(SUPERVISOR_POLL);
+    };
+    // Processes left behind keep the pipes open, so the group goes before the output is read
+    drop(group);
+    if let (None, Some(cgroup), Some(limit_mb)) = (exceeded, &cgroup, limits.memory_limit_mb) {
+        if cgroup.oom_killed() {
+            exceeded = Some(LimitExceeded::Memory(limit_mb));
+        }
+    }
+    drop(cgroup);
+
+    let mut output = stdout_reader.join().unwrap_or_default();
+    output.push_str(&stderr_reader.join().unwrap_or_default());
+
+    // An allocation can also fail inside the process before the poll sees the memory
+    if let (None, Some(limit_mb)) = (exceeded, limits.memory_limit_mb) {
+        if was_aborted(&status, &output) && reports_out_of_memory(&output) {
+            exceeded = Some(LimitExceeded::Memory(limit_mb));
+        }
+    }
+
+    Ok((
+        finish(command_line, working_dir, start, status),
+        output,
+        exceeded,
+    ))
+}
+
+/// How the runtimes report a failed allocation before aborting: Rust's
+/// `memory allocation of N bytes failed`, Node's `JavaScript heap out of memory` and .NET's
+/// `System.OutOfMemoryException`. Only whole messages count, not a test merely named so.
+fn reports_out_of_memory(output: &str) -> bool {
+    output.lines().any(|line| {
+        let is_rust_message = line
+            .trim()
+            .strip_prefix("memory allocation of ")
+            .and_then(|rest| rest.strip_suffix(" bytes failed"))
+            .is_some_and(|bytes| !bytes.is_empty() && bytes.bytes().all(|b| b.is_ascii_digit()));
+        is_rust_message
+            || line.contains("FATAL ERROR:") && line.contains("JavaScript heap out of memory")
+            || line.contains("Unhandled exception. System.OutOfMemoryException")
+    })
+}
+
+/// Whether the command, or the test process under cargo, npm or dotnet, was ended by a signal.
+/// The shells pass an abort on as 134, and the wrappers report it in their output.
+fn was_aborted(status: &ExitStatus, output: &str) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        if status.signal().is_some() {
+            return true;
+        }
+    }
+    status.code() == Some(128 + 6)
+        // cargo's "(signal: 6, SIGABRT: process abort signal)", npm's "signal SIGABRT"
+        || output.contains("signal: 6, SIGABRT")
+        || output.contains("signal SIGABRT")
+        || output.contains("Test host process crashed")
+}
+
+/// How many supervised commands can be killed on a signal at once, across every workspace of
+/// the process. A command beyond that is still killed once its supervision ends.
+const MAX_SUPERVISED_GROUPS: usize = 64;
+
+/// The process groups being supervised, 0 for a free slot. Atomics rather than a lock, so a
+/// signal handler can read them.
+static SUPERVISED_GROUPS: [AtomicI32; MAX_SUPERVISED_GROUPS] =
+    [const { AtomicI32::new(0) }; MAX_SUPERVISED_GROUPS];
+
+/// Kills the process group of a supervised command once dropped, however the supervision ends.
+/// Meanwhile the group is registered for `kill_supervised_groups`.
+struct SupervisedGroup {
+    group: u32,
+    slot: Option<usize>,
+}
+
+impl SupervisedGroup {
+    fn new(group: u32) -> Self {
+        let slot = SUPERVISED_GROUPS.iter().position(|slot| {
+            slot.compare_exchange(0, group as i32, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+        });
+        SupervisedGroup { group, slot }
+    }
+}
+
+impl Drop for SupervisedGroup {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot {
+            SUPERVISED_GROUPS[slot].store(0, Ordering::SeqCst);
+        }
+        #[cfg(unix)]
+        // SAFETY: kill only sends a signal. The group id is the child's pid, see `process_group(0)`
+        unsafe {
+            libc::kill(-(self.group as libc::pid_t), libc::SIGKILL);
+        }
+    }
+}
+
+/// Kills the process groups of every supervised command running, such as tests.
+/// As they run in groups of their own, Ctrl-C in the terminal does not reach them. A program
+/// embedding kata_machine calls this from its own signal handling. It is async-signal-safe.
+pub fn kill_supervised_groups() {
+    for slot in &SUPERVISED_GROUPS {
+        let group = slot.load(Ordering::SeqCst);
+        #[cfg(unix)]
+        if group > 0 {
+            // SAFETY: kill is async-signal-safe and only sends a signal
+            unsafe {
+                libc::kill(-group, libc::SIGKILL);
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = group;
+    }
+}
+
+/// Installs SIGINT and SIGTERM handlers that kill the supervised commands, then let the signal
+/// end the process as it would have. For programs without signal handling of their own, such
+/// as the kata_machine binary.
+pub fn kill_supervised_groups_on_signals() {
+    #[cfg(unix)]
+    {
+        static HANDLERS: std::sync::Once = std::sync::Once::new();
+        HANDLERS.call_once(|| {
+            let handler = kill_groups_and_exit as extern "C" fn(libc::c_int) as *const ()
+                as libc::sighandler_t;
+            // SAFETY: the handler only calls the async-signal-safe kill, signal and raise
+            unsafe {
+                libc::signal(libc::SIGINT, handler);
+                libc::signal(libc::SIGTERM, handler);
+            }
+        });
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn kill_groups_and_exit(signal: libc::c_int) {
+    kill_supervised_groups();
+    // SAFETY: signal and raise are async-signal-safe
+    unsafe {
+        libc::signal(signal, libc::SIG_DFL);
+        libc::raise(signal);
+    }
+}
+
+/// Kills every process in the group the child leads.
+fn kill_process_group(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    {
+        // SAFETY: kill only sends a signal. The group id is the child's pid, see `process_group(0)`
+        unsafe {
+            libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = child.kill();
+    }
+}
+
+/// A cgroup v2 child of kata_machine's own cgroup whose `memory.max` is the memory limit, so
+/// the kernel stops an allocation over it however fast it comes. Removed once dropped.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+struct MemoryCgroup {
+    path: PathBuf,
+}
+
+impl MemoryCgroup {
+    /// `None` unless the cgroup v2 memory controller is delegated to kata_machine's cgroup, as
+    /// for a systemd unit with `Delegate=yes`, or the root of a container.
+    #[cfg(target_os = "linux")]
+    fn create(limit_mb: u64) -> Option<Self> {
+        use std::sync::atomic::AtomicU32;
+        static NEXT: AtomicU32 = AtomicU32::new(0);
+
+        let own = std::fs::read_to_string("/proc/self/cgroup").ok()?;
+        let relative = own.lines().find_map(|line| line.strip_prefix("0::"))?;
+        let parent = PathBuf::from("/sys/fs/cgroup").join(relative.trim_start_matches('/'));
+        let controllers = std::fs::read_to_string(parent.join("cgroup.subtree_control")).ok()?;
+        if !controllers.split_whitespace().any(|name| name == "memory") {
+            return None;
+        }
+
+        let path = parent.join(format!(
+            "kata_machine-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir(&path).ok()?;
+        let cgroup = MemoryCgroup { path };
+        let limit = limit_mb.saturating_mul(1024 * 1024).to_string();
+        std::fs::write(cgroup.path.join("memory.max"), limit).ok()?;
+        // Swapping would only slow the tests down before they hit the limit
+        let _ = std::fs::write(cgroup.path.join("memory.swap.max"), "0");
+        Some(cgroup)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn create(_limit_mb: u64) -> Option<Self> {
+        None
+    }
+
+    fn add(&self, pid: u32) -> bool {
+        std::fs::write(self.path.join("cgroup.procs"), pid.to_string()).is_ok()
+    }
+
+    /// Whether the kernel killed a process of the cgroup for going over `memory.max`.
+    fn oom_killed(&self) -> bool {
+        std::fs::read_to_string(self.path.join("memory.events"))
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.strip_prefix("oom_kill "))
+            .any(|count| count.trim().parse::<u64>().is_ok_and(|count| count > 0))
+    }
+}
+
+impl Drop for MemoryCgroup {
+    fn drop(&mut self) {
+        // The killed processes may take a moment to leave it, until then it cannot be removed
+        for _ in 0..20 {
+            if std::fs::remove_dir(&self.path).is_ok() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+/// The resident memory of every process in the group, read from /proc.
+#[cfg(target_os = "linux")]
+fn process_group_memory(group: u32) -> Option<u64> {
+    // SAFETY: sysconf has no preconditions
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(4096) as u64;
+    let mut pages = 0;
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Ok(stat) = std::fs::read_to_string(entry.path().join("stat")) else {
+            continue;
+        };
+        // The fields after the parenthesized command name: state, ppid, pgrp, ..., rss is the 22nd
+        let Some((_, fields)) = stat.rsplit_once(')') else {
+            continue;
+        };
+        let fields: Vec<&str> = fields.split_whitespace().collect();
+        if fields.get(2).and_then(|pgrp| pgrp.parse::<u32>().ok()) == Some(group) {
+            pages += fields
+                .get(21)
+                .and_then(|rss| rss.parse::<u64>().ok())
+                .unwrap_or(0);
+        }
+    }
+    Some(pages * page_size)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_group_memory(_group: u32) -> Option<u64> {
+    None
+}
+
+fn describe(command: &Command) -> (String, Option<PathBuf>) {
+    let command_line = std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|part| part.to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    (command_line, command.get_current_dir().map(PathBuf::from))
+}
+
+fn program_name(command: &Command) -> String {
+    command.get_program().to_string_lossy().to_string()
+}
+
+fn echo_start(command_line: &str, working_dir: &Option<PathBuf>) {
+    if VERBOSE.load(Ordering::Relaxed) {
+        match working_dir {
+            Some(working_dir) => eprintln!("$ {} (in {})", command_line, working_dir.display()),
+            None => eprintln!("$ {}", command_line),
+        }
+    }
+}
+
+fn finish(
+    command_line: String,
+    working_dir: Option<PathBuf>,
+    start: Instant,
+    status: ExitStatus,
+) -> CommandRecord {
+    let record = CommandRecord {
+        command_line,
+        working_dir,
+        duration: start.elapsed(),
+        status,
+    };
+
+    if VERBOSE.load(Ordering::Relaxed) {
+        eprintln!("{}", record);
+    }
+
+    record
+}
+
+fn tee(mut source: impl Read, mut sink: impl Write) -> String {
+    let mut captured = Vec::new();
+    let mut buffer = [0u8; 4096];
+
+    while let Ok(read) = source.read(&mut buffer) {
+        if read == 0 {
+            break;
+        }
+        let _ = sink.write_all(&buffer[..read]);
+        let _ = sink.flush();
+        captured.extend_from_slice(&buffer[..read]);
+    }
+
+    String::from_utf8_lossy(&captured).to_string()
+}
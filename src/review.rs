@@ -0,0 +1,254 @@
+use crate::{
+    diff::diff_stat,
+    errors::KataError,
+    history::{load_test_runs, read_snapshot_file, TestRun},
+    time_utils::format_duration,
+    FileRole, Kata, Language,
+};
+use std::path::Path;
+
+/// Red to red runs changing more lines than this are reported as big steps.
+const LARGE_DIFF_LINES: usize = 20;
+
+struct Cycle {
+    test_first: bool,
+    tests_added: i64,
+}
+
+pub fn review_day(root: &Path, language: Language, kata: Kata, day: &str) -> Result<(), KataError> {
+    let runs = load_test_runs(root, language, kata, day)?;
+    if runs.is_empty() {
+        return Err(KataError::InvalidInput(format!(
+            "No test runs recorded for {}/{}/{}. Run `kata_machine test` to record them.",
+            language.as_str(),
+            kata.as_str(),
+            day
+        )));
+    }
+
+    println!(
+        "TDD review for {}/{}/{} ({} test runs)",
+        language.as_str(),
+        kata.as_str(),
+        day,
+        runs.len()
+    );
+    println!();
+
+    let cycles = find_cycles(&runs, language);
+    println!("Red to green cycles: {}", cycles.len());
+    if !cycles.is_empty() {
+        let test_first = cycles.iter().filter(|cycle| cycle.test_first).count();
+        let tests_added: i64 = cycles.iter().map(|cycle| cycle.tests_added).sum();
+        println!(
+            "Test written before production code: {}/{} cycles ({}%)",
+            test_first,
+            cycles.len(),
+            test_first * 100 / cycles.len()
+        );
+        println!(
+            "Tests added per cycle: {:.1} on average",
+            tests_added as f64 / cycles.len() as f64
+        );
+    }
+
+    let (red_phases, green_phases) = phase_lengths(&runs);
+    print_phases("Red", &red_phases);
+    print_phases("Green", &green_phases);
+
+    let large_steps = find_large_red_steps(root, &runs)?;
+    println!(
+        "Red to red runs with large diffs (> {} lines): {}",
+        LARGE_DIFF_LINES,
+        large_steps.len()
+    );
+    for (index, lines) in large_steps {
+        println!(
+            "  - run {} -> run {}: {} lines changed",
+            index,
+            index + 1,
+            lines
+        );
+    }
+
+    Ok(())
+}
+
+/// Splits the runs into cycles that each end with a red to green transition.
+fn find_cycles(runs: &[TestRun], language: Language) -> Vec<Cycle> {
+    let mut cycles = Vec::new();
+    let mut cycle_start = 0;
+    let mut previous_total = total_tests(&runs[0]);
+
+    for index in 1..runs.len() {
+        if !runs[index].passed || runs[index - 1].passed {
+            continue;
+        }
+
+        let first_red = runs[cycle_start..index]
+            .iter()
+            .find(|run| !run.passed)
+            .unwrap_or(&runs[index - 1]);
+        let touches = |role| {
+            first_red
+                .changed_files
+                .iter()
+                .any(|path| language.file_role(path) == role)
+        };
+
+        let total = total_tests(&runs[index]);
+        cycles.push(Cycle {
+            test_first: touches(FileRole::Test) && !touches(FileRole::Solution),
+            tests_added: total - previous_total,
+        });
+
+        previous_total = total;
+        cycle_start = index + 1;
+    }
+
+    cycles
+}
+
+/// Returns the durations of the finished red and green phases, in seconds.
+fn phase_lengths(runs: &[TestRun]) -> (Vec<u64>, Vec<u64>) {
+    let (mut red, mut green) = (Vec::new(), Vec::new());
+    let mut phase_start = &runs[0];
+
+    for run in &runs[1..] {
+        if run.passed != phase_start.passed {
+            let length = run.timestamp.saturating_sub(phase_start.timestamp);
+            if phase_start.passed {
+                green.push(length);
+            } else {
+                red.push(length);
+            }
+            phase_start = run;
+        }
+    }
+
+    (red, green)
+}
+
+fn find_large_red_steps(root: &Path, runs: &[TestRun]) -> Result<Vec<(usize, usize)>, KataError> {
+    let mut steps = Vec::new();
+
+    for index in 1..runs.len() {
+        let (previous, current) = (&runs[index - 1], &runs[index]);
+        if previous.passed || current.passed {
+            continue;
+        }
+
+        let mut lines = 0;
+        for path in &current.changed_files {
+            let old_content = match previous.files.get(path) {
+                Some(hash) => read_snapshot_file(root, hash)?,
+                None => String::new(),
+            };
+            let new_content = match current.files.get(path) {
+                Some(hash) => read_snapshot_file(root, hash)?,
+                None => String::new(),
+            };
+            let (added, removed) = diff_stat(&old_content, &new_content);
+            lines += added + removed;
+        }
+
+        if lines > LARGE_DIFF_LINES {
+            steps.push((index, lines));
+        }
+    }
+
+    Ok(steps)
+}
+
+fn print_phases(name: &str, lengths: &[u64]) {
+    if lengths.is_empty() {
+        println!("{} phases: none finished", name);
+        return;
+    }
+
+    let total: u64 = lengths.iter().sum();
+    println!(
+        "{} phases: {}, average {}, longest {}",
+        name,
+        lengths.len(),
+        format_duration(total / lengths.len() as u64),
+        format_duration(*lengths.iter().max().unwrap())
+    );
+}
+
+fn total_tests(run: &TestRun) -> i64 {
+    (run.passed_tests + run.failed_tests) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn run(passed: bool, total: u32, changed_files: &[&str]) -> TestRun {
+        TestRun {
+            timestamp: 0,
+            passed,
+            passed_tests: if passed { total } else { total - 1 },
+            failed_tests: if passed { 0 } else { 1 },
+            limit_exceeded: None,
+            changed_files: changed_files.iter().map(|path| path.to_string()).collect(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    fn cycles(runs: &[TestRun]) -> Vec<(bool, i64)> {
+        find_cycles(runs, Language::Rust)
+            .into_iter()
+            .map(|cycle| (cycle.test_first, cycle.tests_added))
+            .collect()
+    }
+
+    #[test]
+    fn a_red_first_run_starts_the_first_cycle() {
+        let runs = [
+            run(false, 1, &["src/test.rs"]),
+            run(true, 1, &["src/lib.rs"]),
+        ];
+        assert_eq!(cycles(&runs), [(true, 0)]);
+
+        let runs = [
+            run(false, 1, &["src/test.rs"]),
+            run(false, 1, &["src/lib.rs"]),
+            run(true, 1, &["src/lib.rs"]),
+        ];
+        assert_eq!(cycles(&runs), [(true, 0)]);
+    }
+
+    #[test]
+    fn each_red_to_green_transition_ends_a_cycle() {
+        let runs = [
+            run(true, 1, &["src/lib.rs", "src/test.rs"]),
+            run(false, 2, &["src/test.rs"]),
+            run(false, 2, &["src/lib.rs"]),
+            run(true, 2, &["src/lib.rs"]),
+            run(false, 3, &["src/lib.rs", "src/test.rs"]),
+            run(true, 3, &["src/lib.rs"]),
+        ];
+        assert_eq!(cycles(&runs), [(true, 1), (false, 1)]);
+    }
+
+    #[test]
+    fn green_runs_make_no_cycle() {
+        let runs = [
+            run(true, 1, &["src/lib.rs"]),
+            run(true, 2, &["src/test.rs"]),
+        ];
+        assert!(cycles(&runs).is_empty());
+    }
+
+    #[test]
+    fn a_trailing_red_run_is_not_a_cycle() {
+        let runs = [
+            run(false, 1, &["src/test.rs"]),
+            run(true, 1, &["src/lib.rs"]),
+            run(false, 2, &["src/test.rs"]),
+        ];
+        assert_eq!(cycles(&runs), [(true, 0)]);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{
+    copy_day_source_files,
+    errors::KataError,
+    find_most_recent_day_folder,
+    languages::{
+        csharp::commands::{
+            generate_csharp_calculator_reference, write_csharp_calculator_acceptance_tests,
+        },
+        rust::commands::{
+            generate_rust_calculator_reference, use_vendored_registry,
+            write_rust_calculator_acceptance_tests,
+        },
+        typescript::commands::{
+            generate_typescript_calculator_reference, write_typescript_calculator_acceptance_tests,
+        },
+    },
+    run_day_tests, FileRole, Kata, KataInput, Language, LimitExceeded, RunLimits,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The hidden cases of the calculator, as the two numbers and their sum. They never end up in
+/// a day folder, so the visible tests cannot be tuned to them.
+const CALCULATOR_CASES: [(&str, &str, i32); 8] = [
+    ("1", "1", 2),
+    ("42", "0", 42),
+    ("100", "250", 350),
+    ("-7", "-8", -15),
+    ("0", "-1", -1),
+    ("007", "3", 10),
+    ("123456", "654321", 777777),
+    ("2147483646", "1", 2147483647),
+];
+
+/// Runs the hidden acceptance tests of the kata against the solution of the most recent day,
+/// in a copy of the day with its tests swapped out, and prints the score. With `reveal` the
+/// reference solution is printed afterwards, whatever the score.
+pub(crate) fn submit(
+    root: &Path,
+    input: &KataInput,
+    limits: &RunLimits,
+    reveal: bool,
+) -> Result<(), KataError> {
+    let cases: &[(&str, &str, i32)] = match input.kata {
+        Kata::Calculator => &CALCULATOR_CASES,
+        Kata::Dsa | Kata::Blank => {
+            return Err(KataError::InvalidInput(format!(
+                "The {} kata has no acceptance tests, only calculator has.",
+                input.kata.as_str()
+            )))
+        }
+    };
+
+    let language_dir = root.join(input.language.as_str());
+    let kata_dir = language_dir.join(input.kata.as_str());
+    let day_folder_path =
+        find_most_recent_day_folder(&kata_dir).ok_or_else(|| KataError::DayMissing {
+            kata_dir: kata_dir.clone(),
+        })?;
+
+    let scratch_path = scratch_path(root, input.language);
+    if scratch_path.exists() {
+        fs::remove_dir_all(&scratch_path)
+            .map_err(|e| KataError::io("Failed to remove a leftover submission folder", e))?;
+    }
+    let report = build_submission(root, &day_folder_path, &scratch_path, input.language, cases)
+        .and_then(|()| run_day_tests(&scratch_path, input.language, limits));
+    let _ = fs::remove_dir_all(&scratch_path);
+    let report = report?;
+
+    // Extra tests of the user's own, such as Rust doc tests, do not count
+    let total = cases.len() as u32;
+    let passed = if report.success {
+        total
+    } else {
+        report.passed_tests.min(total)
+    };
+    println!(
+        "Score: {}/{} acceptance tests passed ({}%)",
+        passed,
+        total,
+        passed * 100 / total
+    );
+
+    if reveal {
+        println!(
+            "\nReference solution:\n\n{}",
+            reference_solution(input.language)
+        );
+    }
+
+    match report.limit_exceeded {
+        Some(LimitExceeded::Timeout(timeout)) => Err(KataError::TestsTimedOut { timeout }),
+        Some(LimitExceeded::Memory(limit_mb)) => Err(KataError::TestsOutOfMemory { limit_mb }),
+        None if passed == total => Ok(()),
+        None => Err(KataError::TestsFailed {
+            passed,
+            failed: total - passed,
+        }),
+    }
+}
+
+/// The copy of the day `submit` builds. It lives in the language folder, where the shared
+/// node_modules and Directory.Packages.props of the days are found.
+fn scratch_path(root: &Path, language: Language) -> PathBuf {
+    root.join(language.as_str()).join("submission")
+}
+
+/// Copies the day without its tests and writes the hidden tests in their place.
+fn build_submission(
+    root: &Path,
+    day_folder_path: &Path,
+    scratch_path: &Path,
+    language: Language,
+    cases: &[(&str, &str, i32)],
+) -> Result<(), KataError> {
+    fs::create_dir_all(scratch_path)
+        .map_err(|e| KataError::io(format!("Failed to create {}", scratch_path.display()), e))?;
+    copy_day_source_files(day_folder_path, scratch_path, language, FileRole::Test)?;
+
+    match language {
+        Language::Rust => {
+            write_rust_calculator_acceptance_tests(scratch_path, cases)?;
+            use_vendored_registry(root, scratch_path)
+        }
+        Language::TS => write_typescript_calculator_acceptance_tests(scratch_path, cases),
+        Language::CSharp => write_csharp_calculator_acceptance_tests(scratch_path, cases),
+    }
+}
+
+fn reference_solution(language: Language) -> String {
+    match language {
+        Language::Rust => generate_rust_calculator_reference(),
+        Language::TS => generate_typescript_calculator_reference(),
+        Language::CSharp => generate_csharp_calculator_reference(),
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Parses a timeout such as `90`, `90s`, `5m`, `1h`, `2d` or `1w`. A plain number is in seconds.
+/// Zero and durations too long to represent are rejected.
+pub fn parse_timeout(timeout: &str) -> Option<Duration> {
+    let (number, unit) = timeout.split_at(
+        timeout
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(timeout.len()),
+    );
+    let number: u64 = number.parse().ok()?;
+    let unit_secs = match unit {
+        "s" | "" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    number
+        .checked_mul(unit_secs)
+        .map(Duration::from_secs)
+        .filter(|timeout| !timeout.is_zero())
+}
+
+pub fn format_duration(secs: u64) -> String {
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_timeout_units() {
+        assert_eq!(parse_timeout("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_timeout("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_timeout("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_timeout("1h"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_timeout("2d"), Some(Duration::from_secs(2 * 86400)));
+        assert_eq!(parse_timeout("1w"), Some(Duration::from_secs(7 * 86400)));
+    }
+
+    #[test]
+    fn parse_timeout_rejects_invalid_values() {
+        for timeout in ["", "0", "0m", "m", "-5s", "5 m", "1.5h", "5ms", "5x"] {
+            assert_eq!(parse_timeout(timeout), None, "{:?}", timeout);
+        }
+    }
+
+    #[test]
+    fn parse_timeout_rejects_overflows() {
+        assert_eq!(parse_timeout("99999999999999999999"), None);
+        assert_eq!(parse_timeout("99999999999999999w"), None);
+    }
+}
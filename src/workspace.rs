@@ -0,0 +1,686 @@
+use crate::languages::{
+    csharp::{commands::seed_local_package_source, solution::plan_csharp_solution},
+    rust::{cargo_workspace::plan_cargo_workspace, commands::seed_vendored_registry},
+    typescript::commands::install_shared_typescript_dependencies,
+};
+use crate::{
+    clean, compare,
+    config::{run_hook, Config},
+    console_utils, copy_day_source_files, doctor, editor,
+    errors::KataError,
+    file_utils,
+    file_utils::FileBackup,
+    find_day_folder, find_most_recent_day_folder, find_next_day_folder, fuzz, history,
+    install::{plan_install, InitSummary, Tool},
+    install_dependencies, pair, parse_timeout, review, run_day_tests, submit, write_template_files,
+    CleanOptions, DayNaming, DiffFormat, Editor, FileRole, FuzzOptions, InitOptions, Kata,
+    KataInput, Language, LimitExceeded, PairMode, RunLimits, Session, TemplateOptions, TestReport,
+    TsRunner,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// How `generate` sets up the new day.
+#[derive(Default)]
+pub struct GenerateOptions {
+    /// Start from the solution and test files of this earlier day instead of the template.
+    pub from_day: Option<u32>,
+    /// Defaults to the `editor` of the config, then the editor of the session, then VS Code.
+    pub editor: Option<Editor>,
+    /// Make `rust/Cargo.toml` a workspace of all Rust days. Once on, it stays on.
+    pub cargo_workspace: bool,
+    /// Install dependencies from the local caches only.
+    pub offline: bool,
+    pub template: TemplateOptions,
+}
+
+/// The folder holding the generated katas, the session and the recorded test runs.
+pub struct Workspace {
+    root: PathBuf,
+}
+
+impl Workspace {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Workspace { root: root.into() }
+    }
+
+    /// Uses the given root, falling back to `KATA_MACHINE_HOME` and then the current directory.
+    /// The root is made absolute, as the session, the vendored registry and the fuzz harness
+    /// store paths into it that are read from other directories.
+    pub fn locate(root: Option<PathBuf>) -> Result<Self, KataError> {
+        let root = match root.or_else(|| std::env::var_os("KATA_MACHINE_HOME").map(PathBuf::from)) {
+            Some(root) => root,
+            None => std::env::current_dir()
+                .map_err(|e| KataError::io("Failed to get current directory", e))?,
+        };
+
+        if !root.is_dir() {
+            return Err(KataError::InvalidInput(format!(
+                "Workspace root {} is not a directory.",
+                root.display()
+            )));
+        }
+
+        let root = fs::canonicalize(&root)
+            .map_err(|e| KataError::io(format!("Failed to resolve {}", root.display()), e))?;
+        Ok(Workspace::new(root))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The merged user, workspace and environment config. Loaded on demand, so a broken config
+    /// file only gets in the way of the commands that read it.
+    pub fn config(&self) -> Result<Config, KataError> {
+        Config::load(&self.root)
+    }
+
+    /// Reads the language and kata of the most recently generated day from the session.json.
+    pub fn session_input(&self) -> Result<KataInput, KataError> {
+        let session_json = Session::read_json(&self.root)?;
+        let session = Session::parse(&self.root, &session_json)?;
+
+        let corrupt = |field: &str, value: &str| KataError::SessionCorrupt {
+            path: self.root.join("session.json"),
+            source: serde::de::Error::custom(format!("unknown {} {}", field, value)),
+        };
+        let language =
+            Language::get(session.language).ok_or_else(|| corrupt("language", session.language))?;
+        let kata = Kata::get(session.kata).ok_or_else(|| corrupt("kata", session.kata))?;
+
+        Ok(KataInput { language, kata })
+    }
+
+    /// Installs the tools the languages need, then the TypeScript test dependencies shared by all
+    /// days, the C# test packages and the Rust dev-dependencies, so later days are generated without
+    /// touching the network. Ends with a table of what was installed or skipped.
+    pub fn init(&self, options: &InitOptions) -> Result<(), KataError> {
+        let languages: Vec<Language> = Language::ALL
+            .into_iter()
+            .filter(|language| options.languages.is_empty() || options.languages.contains(language))
+            .collect();
+        let tools: Vec<Tool> = Tool::ALL
+            .into_iter()
+            .filter(|tool| {
+                languages
+                    .iter()
+                    .any(|language| language.required_tools().contains(tool))
+            })
+            .collect();
+        let plan = plan_install(&tools, options.user, options.skip_confirmation)?;
+
+        let mut summary = InitSummary::default();
+        let language_names = languages
+            .iter()
+            .map(|language| language.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        for tool in Tool::ALL {
+            if tools.contains(&tool) {
+                summary.add(tool.as_str(), plan.status(tool, options.dry_run));
+            } else {
+                summary.add(
+                    tool.as_str(),
+                    &format!("skipped, not needed for {}", language_names),
+                );
+            }
+        }
+
+        if options.dry_run {
+            plan.print();
+            for language in Language::ALL {
+                if languages.contains(&language) {
+                    println!("Would then {}.", self.seed_description(language));
+                    summary.add(seed_name(language), "would set up");
+                } else {
+                    summary.add(seed_name(language), "skipped");
+                }
+            }
+            summary.print();
+            return Ok(());
+        }
+
+        if !plan.steps.is_empty() && !options.skip_confirmation {
+            plan.print();
+            if !console_utils::confirm("Install the missing tools?")? {
+                println!("Init cancelled.");
+                return Ok(());
+            }
+        }
+        plan.apply()?;
+
+        for language in Language::ALL {
+            if languages.contains(&language) {
+                self.seed(language)?;
+                summary.add(seed_name(language), "set up");
+            } else {
+                summary.add(seed_name(language), "skipped");
+            }
+        }
+
+        summary.print();
+        println!("Initialization completed successfully.");
+        Ok(())
+    }
+
+    /// Fills the local caches of the language, so its days are generated offline.
+    fn seed(&self, language: Language) -> Result<(), KataError> {
+        match language {
+            Language::TS => {
+                println!("Installing the shared TypeScript dependencies...");
+                install_shared_typescript_dependencies(
+                    &self.root.join(Language::TS.as_str()),
+                    &[TsRunner::Jest, TsRunner::Vitest],
+                    false,
+                )
+            }
+            Language::CSharp => {
+                println!("Seeding the local NuGet package source...");
+                seed_local_package_source(&self.root)
+            }
+            Language::Rust => {
+                println!("Vendoring the Rust dev-dependencies...");
+                seed_vendored_registry(&self.root)
+            }
+        }
+    }
+
+    fn seed_description(&self, language: Language) -> String {
+        match language {
+            Language::TS => format!(
+                "install the shared TypeScript dependencies into {}",
+                self.root
+                    .join(Language::TS.as_str())
+                    .join("node_modules")
+                    .display()
+            ),
+            Language::CSharp => format!(
+                "seed the local NuGet package source {}",
+                self.root.join(".kata").join("nuget").display()
+            ),
+            Language::Rust => format!(
+                "vendor the Rust dev-dependencies into {}",
+                self.root.join(".kata").join("vendor").display()
+            ),
+        }
+    }
+
+    /// Checks the tools, the session, the config, the editor settings and the day folders,
+    /// printing how to fix what is wrong. Changes nothing.
+    pub fn doctor(&self) -> Result<(), KataError> {
+        doctor::run_doctor(&self.root)
+    }
+
+    /// The editor the session was generated for, if any.
+    fn session_editor(&self) -> Option<Editor> {
+        let session_json = Session::read_json(&self.root).ok()?;
+        let session = Session::parse(&self.root, &session_json).ok()?;
+        Editor::get(session.editor?)
+    }
+
+    /// Generates the next day of the kata.
+    pub fn generate_kata(
+        &self,
+        input: KataInput,
+        options: &GenerateOptions,
+    ) -> Result<(), KataError> {
+        let config = self.config()?;
+        let from_day = options.from_day;
+        let editor = options
+            .editor
+            .or_else(|| config.text("editor").and_then(Editor::get))
+            .or_else(|| self.session_editor())
+            .unwrap_or(Editor::VsCode);
+        let kata_dir = self
+            .root
+            .join(input.language.as_str())
+            .join(input.kata.as_str());
+
+        let from_day_path = from_day.map(|day| find_day_folder(&kata_dir, day));
+        if let Some(from_day_path) = &from_day_path {
+            if !from_day_path.exists() {
+                return Err(KataError::InvalidInput(format!(
+                    "Day folder {} not found.",
+                    from_day_path.display()
+                )));
+            }
+        }
+
+        let naming = config
+            .text("day_naming")
+            .and_then(DayNaming::get)
+            .unwrap_or_default();
+        let day_folder_path = find_next_day_folder(&kata_dir, naming);
+        let day = day_folder_path.file_name().unwrap().to_owned();
+
+        // Generate into a staging folder first, so a failing step never leaves a broken day behind
+        let staging_path = self
+            .root
+            .join(".kata")
+            .join("staging")
+            .join(input.language.as_str())
+            .join(input.kata.as_str())
+            .join(&day);
+        if staging_path.exists() {
+            fs::remove_dir_all(&staging_path)
+                .map_err(|e| KataError::io("Failed to remove a leftover staging folder", e))?;
+        }
+
+        let staged = self.stage_day(&staging_path, &input, from_day_path.as_deref(), options);
+        let moved = staged.and_then(|()| {
+            fs::create_dir_all(&kata_dir)
+                .and_then(|()| fs::rename(&staging_path, &day_folder_path))
+                .map_err(|e| {
+                    KataError::generation(
+                        "moving the day folder into place",
+                        KataError::io(
+                            format!("Failed to move into {}", day_folder_path.display()),
+                            e,
+                        ),
+                    )
+                })
+        });
+        if let Err(error) = moved {
+            let _ = fs::remove_dir_all(&staging_path);
+            return Err(error);
+        }
+
+        let activated = self.activate_day(&input, &day_folder_path, editor, options);
+        if let Err(error) = activated {
+            let _ = fs::remove_dir_all(&day_folder_path);
+            return Err(error);
+        }
+        history::record_template(&self.root, input.language, input.kata, &day_folder_path)?;
+
+        // The day is in place by now, a failing hook is reported but keeps it
+        run_hook(
+            &self.root,
+            &config,
+            "hooks.post_generate",
+            &hook_env(&input, &day_folder_path),
+        )?;
+
+        Ok(())
+    }
+
+    /// Writes the day's files and installs its dependencies in the staging folder.
+    fn stage_day(
+        &self,
+        staging_path: &Path,
+        input: &KataInput,
+        from_day_path: Option<&Path>,
+        options: &GenerateOptions,
+    ) -> Result<(), KataError> {
+        fs::create_dir_all(staging_path).map_err(|e| {
+            KataError::generation(
+                "creating the staging folder",
+                KataError::io(format!("Failed to create {}", staging_path.display()), e),
+            )
+        })?;
+
+        write_template_files(staging_path, input.language, input.kata, &options.template)
+            .map_err(|e| KataError::generation("writing the template files", e))?;
+
+        if let Some(from_day_path) = from_day_path {
+            copy_day_source_files(
+                from_day_path,
+                staging_path,
+                input.language,
+                FileRole::Config,
+            )
+            .map_err(|e| KataError::generation("copying the files of the earlier day", e))?;
+        }
+
+        install_dependencies(&self.root, staging_path, input.language, options.offline)
+            .map_err(|e| KataError::generation("installing the dependencies", e))
+    }
+
+    /// Adds the newly generated day to the Cargo workspace or the C# solution, the editor settings
+    /// and the session, restoring the files it changed if a step fails.
+    fn activate_day(
+        &self,
+        input: &KataInput,
+        day_folder_path: &Path,
+        editor: Editor,
+        options: &GenerateOptions,
+    ) -> Result<(), KataError> {
+        let mut backups = vec![FileBackup::capture(&[self.root.join("session.json")])];
+
+        let activated = (|| {
+            if input.language == Language::Rust {
+                let cargo_sync = plan_cargo_workspace(&self.root, &[], options.cargo_workspace)
+                    .map_err(|e| {
+                        KataError::generation("adding the day to the Cargo workspace", e)
+                    })?;
+                if let Some(cargo_sync) = cargo_sync {
+                    backups.push(FileBackup::capture(&cargo_sync.paths()));
+                    cargo_sync.apply().map_err(|e| {
+                        KataError::generation("adding the day to the Cargo workspace", e)
+                    })?;
+                }
+            }
+            if input.language == Language::CSharp {
+                let solution_sync = plan_csharp_solution(&self.root, &[])
+                    .map_err(|e| KataError::generation("adding the day to the C# solution", e))?;
+                if let Some(solution_sync) = solution_sync {
+                    backups.push(FileBackup::capture(&solution_sync.paths(&self.root)));
+                    solution_sync.apply(&self.root).map_err(|e| {
+                        KataError::generation("adding the day to the C# solution", e)
+                    })?;
+                }
+            }
+
+            let editor_sync = editor::plan_sync(&self.root, Some(editor), &[])
+                .map_err(|e| KataError::generation("configuring the editor", e))?;
+            backups.push(FileBackup::capture(&editor_sync.paths(&self.root)));
+            editor_sync
+                .apply(&self.root)
+                .map_err(|e| KataError::generation("configuring the editor", e))?;
+
+            let session = Session {
+                language: input.language.as_str(),
+                kata: input.kata.as_str(),
+                day: day_folder_path.to_path_buf(),
+                editor: Some(editor.as_str()),
+            };
+
+            let session_json = serde_json::to_string(&session)
+                .map_err(|err| KataError::json("Error serializing session to JSON", err))?;
+            file_utils::create_file_with_content(&self.root, "session.json", &session_json)
+                .map_err(|e| KataError::generation("writing the session", e))
+        })();
+
+        if activated.is_err() {
+            for backup in backups.iter().rev() {
+                let _ = backup.restore();
+            }
+        }
+        activated
+    }
+
+    /// Runs the tests of the most recent day. The limits left unset come from the config.
+    pub fn run_tests(&self, kata_input: KataInput, limits: RunLimits) -> Result<(), KataError> {
+        let language_dir = self.root.join(kata_input.language.as_str());
+        let kata_dir = language_dir.join(kata_input.kata.as_str());
+
+        let day_folder_path =
+            find_most_recent_day_folder(&kata_dir).ok_or_else(|| KataError::DayMissing {
+                kata_dir: kata_dir.clone(),
+            })?;
+
+        let config = self.config()?;
+        let limits = test_limits(&config, limits);
+        let report = self.test_day(&config, &kata_input, &day_folder_path, &limits)?;
+
+        match report.limit_exceeded {
+            Some(LimitExceeded::Timeout(timeout)) => {
+                return Err(KataError::TestsTimedOut { timeout })
+            }
+            Some(LimitExceeded::Memory(limit_mb)) => {
+                return Err(KataError::TestsOutOfMemory { limit_mb })
+            }
+            None => {}
+        }
+        if report.success {
+            Ok(())
+        } else {
+            Err(KataError::TestsFailed {
+                passed: report.passed_tests,
+                failed: report.failed_tests,
+            })
+        }
+    }
+
+    /// Runs the tests of the day between the test hooks and records the run in the history.
+    fn test_day(
+        &self,
+        config: &Config,
+        kata_input: &KataInput,
+        day_folder_path: &Path,
+        limits: &RunLimits,
+    ) -> Result<TestReport, KataError> {
+        let env = hook_env(kata_input, day_folder_path);
+        run_hook(&self.root, config, "hooks.pre_test", &env)?;
+
+        let report = run_day_tests(day_folder_path, kata_input.language, limits)?;
+        history::record_test_run(
+            &self.root,
+            kata_input.language,
+            kata_input.kata,
+            day_folder_path,
+            &report,
+        )?;
+
+        let result = if report.success { "passed" } else { "failed" };
+        let mut env = env;
+        env.push(("KATA_TEST_RESULT", result.to_string()));
+        let hooked = run_hook(&self.root, config, "hooks.post_test", &env);
+        // A test failure says more than a failing hook
+        if report.success {
+            hooked?;
+        } else if let Err(error) = hooked {
+            eprintln!("{}", error);
+        }
+
+        Ok(report)
+    }
+
+    /// Scores the most recent day against the hidden acceptance tests of its kata, with the
+    /// test limits of the config.
+    pub fn submit(&self, kata_input: KataInput, reveal: bool) -> Result<(), KataError> {
+        let limits = test_limits(&self.config()?, RunLimits::default());
+        submit::submit(&self.root, &kata_input, &limits, reveal)
+    }
+
+    /// Checks the most recent day against the reference of its kata on random inputs, with
+    /// the test limits of the config.
+    pub fn fuzz(&self, kata_input: KataInput, options: &FuzzOptions) -> Result<(), KataError> {
+        let limits = test_limits(&self.config()?, RunLimits::default());
+        fuzz::fuzz(&self.root, &kata_input, &limits, options)
+    }
+
+    pub fn replay(&self, kata_input: KataInput, day: u32) -> Result<(), KataError> {
+        let kata_dir = self
+            .root
+            .join(kata_input.language.as_str())
+            .join(kata_input.kata.as_str());
+        let day_folder_path = find_day_folder(&kata_dir, day);
+        history::replay_day(
+            &self.root,
+            kata_input.language,
+            kata_input.kata,
+            &day_folder_path.file_name().unwrap().to_string_lossy(),
+        )
+    }
+
+    pub fn review(&self, kata_input: KataInput, day: Option<u32>) -> Result<(), KataError> {
+        let language_dir = self.root.join(kata_input.language.as_str());
+        let kata_dir = language_dir.join(kata_input.kata.as_str());
+
+        let day_folder_path = match day {
+            Some(day) => find_day_folder(&kata_dir, day),
+            None => {
+                find_most_recent_day_folder(&kata_dir).ok_or_else(|| KataError::DayMissing {
+                    kata_dir: kata_dir.clone(),
+                })?
+            }
+        };
+        let day = day_folder_path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        review::review_day(&self.root, kata_input.language, kata_input.kata, &day)
+    }
+
+    pub fn diff(
+        &self,
+        kata: Kata,
+        left: (Language, Option<u32>),
+        right: (Language, Option<u32>),
+        format: DiffFormat,
+    ) -> Result<(), KataError> {
+        let resolve_side = |(language, day): (Language, Option<u32>)| {
+            let kata_dir = self.root.join(language.as_str()).join(kata.as_str());
+            let day_folder_path = match day {
+                Some(day) => find_day_folder(&kata_dir, day),
+                None => {
+                    find_most_recent_day_folder(&kata_dir).ok_or_else(|| KataError::DayMissing {
+                        kata_dir: kata_dir.clone(),
+                    })?
+                }
+            };
+
+            if !day_folder_path.exists() {
+                return Err(KataError::InvalidInput(format!(
+                    "Day folder {} not found.",
+                    day_folder_path.display()
+                )));
+            }
+
+            Ok(compare::DaySide {
+                language,
+                day_folder_path,
+            })
+        };
+
+        let left = resolve_side(left)?;
+        let right = resolve_side(right)?;
+        compare::compare_days(&self.root, &left, &right, format)
+    }
+
+    pub fn pair(
+        &self,
+        kata_input: KataInput,
+        participants: Vec<String>,
+        mode: PairMode,
+    ) -> Result<(), KataError> {
+        let language_dir = self.root.join(kata_input.language.as_str());
+        let kata_dir = language_dir.join(kata_input.kata.as_str());
+
+        let day_folder_path =
+            find_most_recent_day_folder(&kata_dir).ok_or_else(|| KataError::DayMissing {
+                kata_dir: kata_dir.clone(),
+            })?;
+
+        let config = self.config()?;
+        let limits = test_limits(&config, RunLimits::default());
+        let day = day_folder_path.file_name().unwrap().to_string_lossy();
+        pair::run_pair_session(
+            &history::pairing_path(&self.root, kata_input.language, kata_input.kata, &day),
+            participants,
+            mode,
+            &mut || self.test_day(&config, &kata_input, &day_folder_path, &limits),
+        )
+    }
+
+    pub fn reset(&self, kata_input: KataInput, skip_confirmation: bool) -> Result<(), KataError> {
+        let language_dir = self.root.join(kata_input.language.as_str());
+        let kata_dir = language_dir.join(kata_input.kata.as_str());
+
+        let day_folder_path =
+            find_most_recent_day_folder(&kata_dir).ok_or_else(|| KataError::DayMissing {
+                kata_dir: kata_dir.clone(),
+            })?;
+
+        if !skip_confirmation
+            && !console_utils::confirm(&format!(
+                "Reset {} to the original template? Your changes will be lost.",
+                day_folder_path.display()
+            ))?
+        {
+            println!("Reset cancelled.");
+            return Ok(());
+        }
+
+        // Keeps the choices the day was generated with, like the C# test framework
+        let template = TemplateOptions::of_day(&day_folder_path, kata_input.language);
+        for relative_path in file_utils::collect_source_files(&day_folder_path)? {
+            fs::remove_file(day_folder_path.join(&relative_path)).map_err(|e| {
+                KataError::io(format!("Failed to remove {}", relative_path.display()), e)
+            })?;
+        }
+        write_template_files(
+            &day_folder_path,
+            kata_input.language,
+            kata_input.kata,
+            &template,
+        )?;
+
+        println!(
+            "Reset {} to the original template.",
+            day_folder_path.display()
+        );
+        Ok(())
+    }
+
+    /// Brings the editor config in line with the existing day folders, leaving the user's own
+    /// entries alone. The editor defaults to the one of the session.
+    pub fn editor_sync(&self, editor: Option<Editor>, dry_run: bool) -> Result<(), KataError> {
+        let editor = editor.or_else(|| self.session_editor());
+        let sync = if dry_run {
+            editor::plan_sync(&self.root, editor, &[])?
+        } else {
+            editor::sync(&self.root, editor)?
+        };
+
+        if sync.is_empty() {
+            println!("The editor config is up to date.");
+            return Ok(());
+        }
+
+        for change in &sync.changes {
+            let path = change.path.strip_prefix(&self.root).unwrap_or(&change.path);
+            println!("{}:", path.display());
+            for line in &change.summary {
+                println!("  {}", line);
+            }
+        }
+        if dry_run {
+            println!("Dry run, nothing was changed.");
+        }
+        Ok(())
+    }
+
+    /// Removes the days selected by the options, their recorded test runs, and the session
+    /// and editor links that point at them.
+    pub fn clean(&self, options: &CleanOptions) -> Result<(), KataError> {
+        clean::clean(&self.root, options)
+    }
+}
+
+/// The summary row of the caches `init` fills for the language.
+fn seed_name(language: Language) -> &'static str {
+    match language {
+        Language::TS => "typescript dependencies",
+        Language::CSharp => "csharp packages",
+        Language::Rust => "rust dev-dependencies",
+    }
+}
+
+/// The limits given on the command line, completed from the config.
+fn test_limits(config: &Config, limits: RunLimits) -> RunLimits {
+    RunLimits {
+        timeout: limits
+            .timeout
+            .or_else(|| config.text("test.timeout").and_then(parse_timeout)),
+        memory_limit_mb: limits.memory_limit_mb.or_else(|| {
+            config
+                .text("test.memory_limit")
+                .and_then(|limit_mb| limit_mb.parse().ok())
+        }),
+    }
+}
+
+/// The environment the hook scripts run with.
+fn hook_env(input: &KataInput, day_folder_path: &Path) -> Vec<(&'static str, String)> {
+    vec![
+        ("KATA_LANGUAGE", input.language.as_str().to_string()),
+        ("KATA_KATA", input.kata.as_str().to_string()),
+        ("KATA_DAY", day_folder_path.to_string_lossy().to_string()),
+    ]
+}